@@ -0,0 +1,3 @@
+-- Add migration script here
+alter table cards add column collector_number text;
+alter table cards add column mtgo_id integer;
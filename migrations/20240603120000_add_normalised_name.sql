@@ -0,0 +1,7 @@
+-- Add migration script here
+alter table cards add column normalised_name text;
+
+-- Best effort until the next sync populates the fully normalised names
+update cards set normalised_name = lower(name);
+
+create index if not exists idx_normalised_name on cards(normalised_name);
@@ -0,0 +1,12 @@
+-- Add migration script here
+alter table cards add column oracle_text text;
+
+create virtual table if not exists cards_fts using fts5(
+    normalised_name,
+    type_line,
+    oracle_text,
+    content = 'cards',
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+insert into cards_fts(cards_fts) values('rebuild');
@@ -0,0 +1,11 @@
+-- Add migration script here
+alter table cards add column mana_cost text;
+alter table cards add column cmc real;
+alter table cards add column colors text;
+alter table cards add column color_identity text;
+alter table cards add column rarity text;
+alter table cards add column artist text;
+alter table cards add column legalities text;
+
+create index if not exists idx_cmc on cards(cmc);
+create index if not exists idx_rarity on cards(rarity);
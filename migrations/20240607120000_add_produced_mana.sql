@@ -0,0 +1,2 @@
+-- Add migration script here
+alter table cards add column produced_mana text;
@@ -0,0 +1,17 @@
+-- Add migration script here
+-- Cards are re-created on every sync, so owned printings are keyed by name,
+-- set and collector number rather than the card id
+create table if not exists collection (
+    id integer primary key autoincrement,
+    name text not null,
+    normalised_name text not null,
+    set_tag text not null default '',
+    collector_number text not null default '',
+    finish text not null default 'nonfoil',
+    condition text not null default 'near_mint',
+    language text not null default 'en',
+    quantity integer not null,
+    unique(normalised_name, set_tag, collector_number, finish, condition, language)
+);
+
+create index if not exists idx_collection_name on collection(normalised_name);
@@ -0,0 +1,6 @@
+-- Add migration script here
+-- Populated on the next sync, used to match scanner app exports to printings
+alter table cards add column scryfall_id text;
+
+create index if not exists idx_scryfall_id on cards(scryfall_id);
+create index if not exists idx_printing on cards(set_tag, collector_number);
@@ -0,0 +1,14 @@
+-- Add migration script here
+-- Amounts are the total paid or received for all copies in the entry
+create table if not exists ledger (
+    id integer primary key autoincrement,
+    name text not null,
+    normalised_name text not null,
+    kind text not null,
+    quantity integer not null,
+    amount real not null,
+    currency text not null,
+    date text not null
+);
+
+create index if not exists idx_ledger_name on ledger(normalised_name);
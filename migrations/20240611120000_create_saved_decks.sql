@@ -0,0 +1,30 @@
+-- Add migration script here
+-- Every save of a deck adds a revision, so earlier versions can still be loaded
+create table if not exists decks (
+    id integer primary key autoincrement,
+    name text not null unique collate nocase,
+    -- Last revision number handed out, so removed revisions are never reused
+    last_revision integer not null default 0
+);
+
+create table if not exists deck_revisions (
+    id integer primary key autoincrement,
+    deck_id integer not null references decks(id) on delete cascade,
+    revision integer not null,
+    saved_at text not null default (datetime('now', 'localtime')),
+    unique(deck_id, revision)
+);
+
+create table if not exists deck_cards (
+    id integer primary key autoincrement,
+    revision_id integer not null references deck_revisions(id) on delete cascade,
+    quantity integer not null,
+    name text not null,
+    section text not null,
+    set_tag text,
+    collector_number text,
+    foil boolean not null default false,
+    mtgo_id integer
+);
+
+create index if not exists idx_deck_cards_revision on deck_cards(revision_id);
@@ -0,0 +1,14 @@
+-- Add migration script here
+-- Value of the latest revision of each saved deck, recorded on every sync
+create table if not exists deck_values (
+    id integer primary key autoincrement,
+    deck_id integer not null references decks(id) on delete cascade,
+    revision integer not null,
+    currency text not null,
+    value real not null,
+    -- Copies of cards left out of the value for having no price
+    unpriced integer not null default 0,
+    recorded_at text not null default (datetime('now', 'localtime'))
+);
+
+create index if not exists idx_deck_values_deck on deck_values(deck_id, currency);
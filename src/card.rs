@@ -3,8 +3,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use std::collections::HashMap;
 
-pub(crate) const BASIC_LANDS: [&str; 6] =
-    ["swamp", "island", "mountain", "plains", "forest", "wastes"];
+use crate::utils::is_basic_land;
 
 pub(crate) const POWER: [&str; 9] = [
     "black lotus",
@@ -18,7 +17,8 @@ pub(crate) const POWER: [&str; 9] = [
     "time walk",
 ];
 
-pub(crate) type DeckEntry = (i8, String);
+/// Colours in WUBRG order, as stored in the `colors` / `color_identity` columns
+pub(crate) const COLOURS: [char; 5] = ['W', 'U', 'B', 'R', 'G'];
 
 pub(crate) type StoreValue = (
     Option<String>,
@@ -26,9 +26,11 @@ pub(crate) type StoreValue = (
     Option<String>,
     Option<f32>,
     Option<String>,
+    Option<String>,
 );
 
-#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, sqlx::Type)]
+#[sqlx(rename_all = "snake_case")]
 pub(crate) enum Currency {
     Euro,
     #[clap(name = "euro_foil")]
@@ -41,8 +43,55 @@ pub(crate) enum Currency {
     Tix,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(rename_all = "snake_case")]
+pub(crate) enum DeckSection {
+    Main,
+    Sideboard,
+    Commander,
+}
+
+impl std::fmt::Display for DeckSection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let section = match self {
+            Self::Main => "Main",
+            Self::Sideboard => "Sideboard",
+            Self::Commander => "Commander",
+        };
+
+        write!(f, "{section}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DeckEntry {
+    pub(crate) quantity: u32,
+    pub(crate) name: String,
+    pub(crate) section: DeckSection,
+    pub(crate) set: Option<String>,
+    pub(crate) collector_number: Option<String>,
+    pub(crate) foil: bool,
+    /// MTGO catalogue ID (`CatID` in `.dek` files)
+    pub(crate) mtgo_id: Option<u32>,
+}
+
+impl DeckEntry {
+    pub(crate) fn new(quantity: u32, name: impl Into<String>, section: DeckSection) -> Self {
+        Self {
+            quantity,
+            name: name.into(),
+            section,
+            set: None,
+            collector_number: None,
+            foil: false,
+            mtgo_id: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct Deck {
+    pub(crate) name: Option<String>,
     pub(crate) cards: Vec<DeckEntry>,
     pub(crate) contains_power: bool,
 }
@@ -51,7 +100,7 @@ impl Deck {
     pub(crate) fn new(cards: Vec<DeckEntry>) -> Self {
         let mut contains_power = false;
         'outer: for card in cards.iter() {
-            let (_, name) = card;
+            let name = &card.name;
             for power in POWER {
                 if name.to_lowercase().contains(power) {
                     contains_power = true;
@@ -61,6 +110,7 @@ impl Deck {
         }
 
         Self {
+            name: None,
             cards,
             contains_power,
         }
@@ -69,21 +119,67 @@ impl Deck {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Card {
+    pub(crate) id: String,
     pub(crate) name: String,
     pub(crate) set: String,
     pub(crate) set_name: String,
+    pub(crate) collector_number: String,
+    pub(crate) mtgo_id: Option<u32>,
+    pub(crate) type_line: Option<String>,
+    pub(crate) oracle_text: Option<String>,
+    pub(crate) mana_cost: Option<String>,
+    pub(crate) cmc: Option<f32>,
+    pub(crate) colors: Option<Vec<String>>,
+    #[serde(default)]
+    pub(crate) color_identity: Vec<String>,
+    pub(crate) rarity: Option<String>,
+    pub(crate) artist: Option<String>,
+    #[serde(default)]
+    pub(crate) legalities: HashMap<String, String>,
+    #[serde(default)]
+    pub(crate) keywords: Vec<String>,
+    pub(crate) produced_mana: Option<Vec<String>>,
+    pub(crate) card_faces: Option<Vec<CardFace>>,
     #[serde(rename = "purchase_uris")]
     pub(crate) purchase_links: Option<HashMap<String, String>>,
     pub(crate) prices: HashMap<String, Option<String>>,
 }
 
+/// A single face of a multi-faced card (split, transform, adventure etc.)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CardFace {
+    pub(crate) name: String,
+    pub(crate) type_line: Option<String>,
+    pub(crate) oracle_text: Option<String>,
+    pub(crate) mana_cost: Option<String>,
+    pub(crate) colors: Option<Vec<String>>,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow)]
 pub(crate) struct DbCard {
     pub(crate) id: Option<String>,
+    /// Scryfall id of the synced printing
+    pub(crate) scryfall_id: Option<String>,
     pub(crate) name: Option<String>,
     #[sqlx(rename = "set_tag")]
     pub(crate) set: Option<String>,
     pub(crate) set_name: Option<String>,
+    pub(crate) collector_number: Option<String>,
+    pub(crate) mtgo_id: Option<u32>,
+    pub(crate) type_line: Option<String>,
+    pub(crate) oracle_text: Option<String>,
+    pub(crate) mana_cost: Option<String>,
+    pub(crate) cmc: Option<f32>,
+    pub(crate) colors: Option<String>,
+    pub(crate) color_identity: Option<String>,
+    pub(crate) rarity: Option<String>,
+    pub(crate) artist: Option<String>,
+    /// Format legalities as a JSON object, e.g. `{"modern": "legal"}`
+    pub(crate) legalities: Option<String>,
+    /// Keyword abilities as a JSON array, e.g. `["Flying", "Haste"]`
+    pub(crate) keywords: Option<String>,
+    /// Colours of mana the card can produce in WUBRGC order (e.g. `UR` for Steam Vents)
+    pub(crate) produced_mana: Option<String>,
     pub(crate) euro: Option<f32>,
     pub(crate) euro_foil: Option<f32>,
     pub(crate) usd: Option<f32>,
@@ -137,14 +233,90 @@ impl std::fmt::Display for DbCard {
     }
 }
 
+impl DbCard {
+    /// Checks the front face type line for a type, supertype or subtype
+    /// (e.g. `Creature`, `Legendary` or `Time Lord Doctor`)
+    pub(crate) fn has_type(&self, card_type: &str) -> bool {
+        let type_line = self.type_line.as_deref().unwrap_or_default();
+        let front = type_line.split(" // ").next().unwrap_or_default();
+
+        if card_type.contains(' ') {
+            return front.contains(card_type);
+        }
+
+        front
+            .split(|c: char| c.is_whitespace() || c == '—')
+            .any(|t| t == card_type)
+    }
+
+    pub(crate) fn is_basic_land(&self) -> bool {
+        self.type_line.as_deref().is_some_and(is_basic_land)
+    }
+
+    pub(crate) fn price(&self, currency: Currency) -> Option<f32> {
+        match currency {
+            Currency::Euro => self.euro,
+            Currency::EuroFoil => self.euro_foil,
+            Currency::Usd => self.usd,
+            Currency::UsdFoil => self.usd_foil,
+            Currency::UsdEtched => self.usd_etched,
+            Currency::Tix => self.tix,
+        }
+    }
+}
+
 impl Card {
-    pub(crate) fn to_db_entry(self) -> DbCard {
-        let mut card = DbCard::default();
+    pub(crate) fn into_db_entry(self) -> DbCard {
+        // Multi-faced cards only have oracle text on each face
+        let oracle_text = self.oracle_text.or_else(|| {
+            let faces = self.card_faces.as_ref()?;
+            let text: Vec<&str> = faces
+                .iter()
+                .filter_map(|face| face.oracle_text.as_deref())
+                .collect();
 
-        card.id = Some(uuid::Uuid::new_v4().to_string());
-        card.name = Some(self.name);
-        card.set = Some(self.set.to_uppercase());
-        card.set_name = Some(self.set_name);
+            Some(text.join("\n//\n"))
+        });
+
+        let faces = self.card_faces.as_deref().unwrap_or_default();
+        let mana_cost = self.mana_cost.filter(|cost| !cost.is_empty()).or_else(|| {
+            let costs: Vec<&str> = faces
+                .iter()
+                .filter_map(|face| face.mana_cost.as_deref())
+                .filter(|cost| !cost.is_empty())
+                .collect();
+
+            (!costs.is_empty()).then(|| costs.join(" // "))
+        });
+
+        let colors = self.colors.unwrap_or_else(|| {
+            faces
+                .iter()
+                .flat_map(|face| face.colors.clone().unwrap_or_default())
+                .collect()
+        });
+
+        let mut card = DbCard {
+            id: Some(uuid::Uuid::new_v4().to_string()),
+            scryfall_id: Some(self.id),
+            name: Some(self.name),
+            set: Some(self.set.to_uppercase()),
+            set_name: Some(self.set_name),
+            collector_number: Some(self.collector_number),
+            mtgo_id: self.mtgo_id,
+            type_line: self.type_line,
+            oracle_text,
+            mana_cost,
+            cmc: self.cmc,
+            colors: Some(colour_string(&colors)),
+            color_identity: Some(colour_string(&self.color_identity)),
+            rarity: self.rarity,
+            artist: self.artist,
+            legalities: serde_json::to_string(&self.legalities).ok(),
+            keywords: serde_json::to_string(&self.keywords).ok(),
+            produced_mana: self.produced_mana.as_deref().map(mana_string),
+            ..Default::default()
+        };
         if let Some(p_links) = self.purchase_links {
             for (key, value) in p_links.into_iter() {
                 match key.as_str() {
@@ -186,11 +358,12 @@ pub(crate) struct PricedCard {
     pub(crate) price: Option<f32>,
     pub(crate) currency: Currency,
     pub(crate) purchase_site: Option<String>,
+    pub(crate) type_line: Option<String>,
 }
 
 impl PricedCard {
     pub fn new(entry: StoreValue, currency: Currency) -> Self {
-        let (name, set_tag, set_name, price, purchase_site) = entry;
+        let (name, set_tag, set_name, price, purchase_site, type_line) = entry;
         Self {
             name,
             set_tag,
@@ -198,8 +371,13 @@ impl PricedCard {
             price,
             currency,
             purchase_site,
+            type_line,
         }
     }
+
+    pub(crate) fn is_basic_land(&self) -> bool {
+        self.type_line.as_deref().is_some_and(is_basic_land)
+    }
 }
 
 impl std::fmt::Display for PricedCard {
@@ -216,14 +394,14 @@ impl std::fmt::Display for PricedCard {
 }
 
 impl Currency {
-    pub(crate) fn to_price(&self, price: Option<f32>) -> String {
+    pub(crate) fn to_price(self, price: Option<f32>) -> String {
         let price = if let Some(price) = price {
             format!("{price:.2}")
         } else {
             "N/A".to_string()
         };
 
-        match *self {
+        match self {
             Self::Euro => format!("{price}€"),
             Self::EuroFoil => format!("{price}€ (Foil)"),
             Self::Usd => format!("${price}"),
@@ -233,19 +411,38 @@ impl Currency {
         }
     }
 
-    pub(crate) fn to_string(&self) -> String {
-        match *self {
-            Self::Euro => "euro".to_string(),
-            Self::EuroFoil => "euro_foil".to_string(),
-            Self::Usd => "usd".to_string(),
-            Self::UsdFoil => "usd_foil".to_string(),
-            Self::UsdEtched => "usd_etched".to_string(),
-            Self::Tix => "tix".to_string(),
+    /// The currency without a finish, e.g. euro for foil prices in euro
+    pub(crate) fn base(self) -> Self {
+        match self {
+            Self::Euro | Self::EuroFoil => Self::Euro,
+            Self::Usd | Self::UsdFoil | Self::UsdEtched => Self::Usd,
+            Self::Tix => Self::Tix,
+        }
+    }
+
+    /// Price column in the `cards` table for this currency
+    pub(crate) fn column(self) -> &'static str {
+        match self {
+            Self::Euro => "euro",
+            Self::EuroFoil => "euro_foil",
+            Self::Usd => "usd",
+            Self::UsdFoil => "usd_foil",
+            Self::UsdEtched => "usd_etched",
+            Self::Tix => "tix",
+        }
+    }
+
+    /// Purchase link column in the `cards` table for this currency
+    pub(crate) fn purchase_site(self) -> &'static str {
+        match self {
+            Self::Euro | Self::EuroFoil => "cardmarket",
+            Self::Usd | Self::UsdFoil | Self::UsdEtched => "tcgplayer",
+            Self::Tix => "cardhoarder",
         }
     }
 
-    pub(crate) fn to_purchase_location(&self) -> String {
-        match *self {
+    pub(crate) fn to_purchase_location(self) -> String {
+        match self {
             Self::Euro | Self::EuroFoil => "Check https://www.cardmarket.com/en/Magic for buying options".to_string(),
             Self::Usd| Self::UsdFoil | Self::UsdEtched => "Check https://www.tcgplayer.com/search/magic/product?productLineName=magic&page=1&view=grid for buying options".to_string(),
             Self::Tix => "Check https://www.cardhoarder.com/ for buying options".to_string()
@@ -253,6 +450,24 @@ impl Currency {
     }
 }
 
+/// Converts Scryfall colour lists into a WUBRG ordered string (e.g. `["G", "U"]` -> `UG`)
+fn colour_string(colours: &[String]) -> String {
+    COLOURS
+        .iter()
+        .filter(|colour| colours.iter().any(|c| c.starts_with(**colour)))
+        .collect()
+}
+
+/// Like [`colour_string`] but keeps colourless mana as a trailing `C`
+fn mana_string(mana: &[String]) -> String {
+    let mut mana_string = colour_string(mana);
+    if mana.iter().any(|m| m == "C") {
+        mana_string.push('C');
+    }
+
+    mana_string
+}
+
 pub(crate) fn filter_cards(cards: Vec<Card>) -> Vec<DbCard> {
     let filtered: Vec<DbCard> = cards
         .into_iter()
@@ -263,7 +478,7 @@ pub(crate) fn filter_cards(cards: Vec<Card>) -> Vec<DbCard> {
                 return None;
             }
 
-            Some(card.to_db_entry())
+            Some(card.into_db_entry())
         })
         .collect();
 
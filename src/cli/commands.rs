@@ -1,11 +1,30 @@
 use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::path::Path;
 use tokio::fs;
 
 use crate::{
-    card::Currency,
+    card::{Currency, DbCard, Deck, DeckSection},
+    cli::{
+        CollectionCommands, CollectionEntry, DeckCommands, LedgerCommands, LedgerRecord,
+        PriceOptions, SimulateOptions,
+    },
+    collection::{CollectionCard, Finish},
+    diff::{DeckDiff, SECTIONS},
+    formats::{self, DeckFormat},
+    fuzzy::{format_suggestions, FuzzyMatcher},
+    ledger::{self, format_gain, Transaction},
+    legality::{check_deck, Format, Legality},
     loader::download_cards,
-    store::MageDeck,
-    utils::{self, get_project_dir, sanitise},
+    manabase::ManaBase,
+    plan::{BuildPlan, MAX_DECKS},
+    query::{SearchQuery, SortOrder},
+    saved::{self, percent_change, VALUE_CHANGE_THRESHOLD, VALUE_CURRENCIES},
+    scanner::{self, MatchMethod, ScannedCard, ScannerApp},
+    simulate::{hypergeometric_at_least, KeepRange, Simulator},
+    stats::DeckStats,
+    store::{MageDeck, Resolution},
+    utils::{format_candidates, get_project_dir, normalise_name},
 };
 
 fn is_initialised() -> Result<bool> {
@@ -24,18 +43,20 @@ pub(crate) async fn init() -> Result<()> {
     if !project_dir.exists() {
         fs::create_dir(&project_dir)
             .await
-            .with_context(|| format!("creating magedeck directory"))?;
+            .context("creating magedeck directory")?;
     }
 
     MageDeck::load().await.context("loading db")?;
 
     println!("[*] Initialised MageDeck at {}", project_dir.display());
-    sync().await.context("syncing database")?;
+    sync(VALUE_CHANGE_THRESHOLD, Currency::Euro)
+        .await
+        .context("syncing database")?;
 
     Ok(())
 }
 
-pub(crate) async fn sync() -> Result<()> {
+pub(crate) async fn sync(threshold: f32, currency: Currency) -> Result<()> {
     if !is_initialised()? {
         return Ok(());
     }
@@ -46,6 +67,81 @@ pub(crate) async fn sync() -> Result<()> {
         .context("downloading bulk data from scryfall")?;
 
     db.sync(cards).await.context("syncing data with db")?;
+    record_deck_values(&mut db, threshold, currency)
+        .await
+        .context("recording saved deck values")?;
+
+    Ok(())
+}
+
+/// Records the value of every saved deck with the newly synced prices, listing
+/// the decks whose value in `currency` changed by more than `threshold` percent
+async fn record_deck_values(db: &mut MageDeck, threshold: f32, currency: Currency) -> Result<()> {
+    let decks = db.get_saved_decks().await?;
+    if decks.is_empty() {
+        return Ok(());
+    }
+
+    println!("[*] Recording saved deck values...");
+    let currency = currency.base();
+    let mut changed = Vec::new();
+    for saved in decks {
+        let Some((revision, deck)) = db.get_saved_deck(&saved.name, None).await? else {
+            continue;
+        };
+
+        for value_currency in VALUE_CURRENCIES {
+            let previous = db.latest_deck_value(&saved.name, value_currency).await?;
+            let (value, unpriced) = deck_value(db, &deck, value_currency).await?;
+            db.record_deck_value(&saved.name, revision, value_currency, value, unpriced)
+                .await?;
+
+            if value_currency != currency {
+                continue;
+            }
+
+            let Some(previous) = previous else {
+                continue;
+            };
+
+            let change = percent_change(previous.value, value);
+            if change.is_some_and(|change| change.abs() > threshold) {
+                let mut line = format!(
+                    "{}: {} -> {} ({})",
+                    saved.name,
+                    currency.to_price(Some(previous.value)),
+                    currency.to_price(Some(value)),
+                    format_percent(change.unwrap_or_default())
+                );
+
+                if previous.revision != revision {
+                    line.push_str(&format!(
+                        ", saved revision {} -> {revision}",
+                        previous.revision
+                    ));
+                }
+
+                // Cards gaining or losing a price change the value without any
+                // price changing
+                if previous.unpriced != unpriced {
+                    line.push_str(&format!(
+                        ", cards without a price {} -> {unpriced}",
+                        previous.unpriced
+                    ));
+                }
+                changed.push(line);
+            }
+        }
+    }
+
+    if changed.is_empty() {
+        println!("[*] No saved deck values changed by more than {threshold}%");
+    } else {
+        println!("\n[*] Saved decks whose value changed by more than {threshold}%:");
+        for line in changed {
+            println!("[*]   {line}");
+        }
+    }
 
     Ok(())
 }
@@ -60,12 +156,11 @@ pub(crate) async fn clean() -> Result<()> {
     Ok(())
 }
 
-// TODO: Cleanup and split out
 pub(crate) async fn price(
     card: Option<String>,
     deck: Option<String>,
-    currency: Currency,
-    exact_match: bool,
+    deck_format: Option<DeckFormat>,
+    options: PriceOptions,
 ) -> Result<()> {
     if !is_initialised()? {
         return Ok(());
@@ -73,82 +168,1595 @@ pub(crate) async fn price(
 
     let mut db = MageDeck::load().await.context("loading db")?;
     if let Some(name) = card {
-        let name = sanitise(&name);
-        match db.get_cheapest_card(&name, currency, exact_match).await? {
-            Some(card) => println!("[*] {card} ({})", card.purchase_site.as_ref().unwrap()),
-            None => println!("[*] No entry found for '{name}'"),
-        }
+        price_card(&mut db, &name, &options).await?;
     } else if let Some(deck) = deck {
-        let loaded_deck = utils::load_deck(&deck).await?;
-        if loaded_deck.contains_power && currency != Currency::Tix {
-            println!("[*] Cheapest version of deck '{deck}': You added power and expected this to be cheap...? Away and chase yersel...");
-            return Ok(());
-        }
-        let mut total_price = 0.0;
-        let mut cheapest = (String::new(), f32::MAX);
-        let mut most_expensive = (String::new(), 0.0);
-        for card in loaded_deck.cards.into_iter() {
-            let (quantity, name) = card;
-            match db.get_cheapest_card(&name, currency, exact_match).await? {
-                Some(entry) => {
-                    if let Some(mut price) = entry.price {
-                        if price < cheapest.1 {
-                            cheapest = (entry.name.as_ref().unwrap().to_string(), price);
-                        }
-
-                        if price > most_expensive.1 {
-                            most_expensive = (entry.name.as_ref().unwrap().to_string(), price);
-                        }
-                        price *= quantity as f32;
-                        total_price += price;
-                        println!(
-                            "[*] {quantity}x {} - {} ({}): {}",
-                            entry.name.unwrap(),
-                            entry.set_name.unwrap(),
-                            entry.set_tag.unwrap().to_uppercase(),
-                            currency.to_price(Some(price))
-                        );
+        price_deck(&mut db, &deck, deck_format, &options).await?;
+    } else {
+        println!("[*] Need either `--deck` or `--card` argument to be set!");
+    }
+
+    Ok(())
+}
+
+async fn price_card(db: &mut MageDeck, name: &str, options: &PriceOptions) -> Result<()> {
+    let Some(resolved) = resolve_card_name(db, name, options.exact_match).await? else {
+        return Ok(());
+    };
+
+    match db.get_cheapest_card(&resolved, options.currency).await? {
+        Some(card) => println!("[*] {card} ({})", card.purchase_site.as_ref().unwrap()),
+        None => println!("[*] No entry found for '{name}'"),
+    }
+
+    Ok(())
+}
+
+async fn price_deck(
+    db: &mut MageDeck,
+    deck: &str,
+    deck_format: Option<DeckFormat>,
+    options: &PriceOptions,
+) -> Result<()> {
+    let currency = options.currency;
+    let loaded_deck = load_deck(Some(db), deck, deck_format).await?;
+    if loaded_deck.contains_power && currency != Currency::Tix {
+        println!("[*] Cheapest version of deck '{deck}': You added power and expected this to be cheap...? Away and chase yersel...");
+        return Ok(());
+    }
+
+    // Copies in the collection not yet used by an earlier entry of the deck
+    let mut owned = if options.owned {
+        owned_copies(db, &options.finishes).await?
+    } else {
+        Vec::new()
+    };
+
+    let mut total_price = 0.0;
+    let mut owned_totals = (0, 0.0);
+    let mut to_buy_totals = (0, 0.0);
+    let mut cheapest = (String::new(), f32::MAX);
+    let mut most_expensive = (String::new(), 0.0);
+    let mut matcher = None;
+    let mut unresolved = Vec::new();
+    for card in loaded_deck.cards.into_iter() {
+        let quantity = card.quantity;
+        let name = card.name;
+        let resolved = match db.resolve_name(&name, options.exact_match).await? {
+            Resolution::Found(resolved) => resolved,
+            Resolution::Ambiguous(candidates) => {
+                let candidates = format_candidates(&candidates);
+                println!("[*] '{name}' is ambiguous, matching: {candidates}");
+                unresolved.push(format!("{quantity}x {name} (ambiguous: {candidates})"));
+                continue;
+            }
+            Resolution::NotFound => {
+                // Card names are only loaded for fuzzy matching once something is missing
+                if matcher.is_none() {
+                    matcher = Some(FuzzyMatcher::new(db.get_card_names().await?));
+                }
+
+                let suggestions = matcher.as_ref().unwrap().suggestions(&name);
+                match FuzzyMatcher::unique_match(&suggestions).filter(|_| options.fix) {
+                    Some(suggestion) => {
+                        println!("[*] Using '{}' for '{name}'", suggestion.name);
+                        suggestion.name.clone()
+                    }
+                    None => {
+                        let suggestions = format_suggestions(&suggestions);
+                        println!("[*] No entry found for '{name}'{suggestions}");
+                        unresolved.push(format!("{quantity}x {name}{suggestions}"));
+                        continue;
                     }
                 }
-                None => println!("[*] No entry found for '{name}'"),
             }
+        };
+
+        let Some(entry) = db.get_cheapest_card(&resolved, currency).await? else {
+            println!("[*] No entry found for '{resolved}'");
+            unresolved.push(format!("{quantity}x {name} (not found)"));
+            continue;
+        };
+
+        if !options.include_basics && entry.is_basic_land() {
+            continue;
+        }
+
+        let Some(mut price) = entry.price else {
+            println!("[*] No price found for '{resolved}'");
+            unresolved.push(format!("{quantity}x {name} (no price)"));
+            continue;
+        };
+
+        if price < cheapest.1 {
+            cheapest = (entry.name.as_ref().unwrap().to_string(), price);
+        }
+
+        if price > most_expensive.1 {
+            most_expensive = (entry.name.as_ref().unwrap().to_string(), price);
+        }
+        let owned_quantity = if options.owned {
+            take_owned(&mut owned, &resolved, quantity)
+        } else {
+            0
+        };
+        let to_buy = quantity - owned_quantity;
+        owned_totals.0 += owned_quantity;
+        owned_totals.1 += price * owned_quantity as f32;
+        to_buy_totals.0 += to_buy;
+        to_buy_totals.1 += price * to_buy as f32;
+
+        price *= quantity as f32;
+        total_price += price;
+        print!(
+            "[*] {quantity}x {} - {} ({}): {}",
+            entry.name.unwrap(),
+            entry.set_name.unwrap(),
+            entry.set_tag.unwrap().to_uppercase(),
+            currency.to_price(Some(price))
+        );
+
+        match (options.owned, owned_quantity) {
+            (false, _) => println!(),
+            (true, 0) => println!(" (to buy)"),
+            (true, owned) if owned == quantity => println!(" (owned)"),
+            (true, owned) => println!(" ({owned} owned, {to_buy} to buy)"),
         }
+    }
+    println!(
+        "\n[*] Cheapest version of deck '{deck}': {}",
+        currency.to_price(Some(total_price))
+    );
+    println!(
+        "[*] Cheapest card: {} {}",
+        cheapest.0,
+        currency.to_price(Some(cheapest.1))
+    );
+    println!(
+        "[*] Most expensive card: {} {}",
+        most_expensive.0,
+        currency.to_price(Some(most_expensive.1))
+    );
+    println!("[*] {}", currency.to_purchase_location());
+
+    if options.owned {
+        println!(
+            "\n[*] Already owned: {} cards ({})",
+            owned_totals.0,
+            currency.to_price(Some(owned_totals.1))
+        );
         println!(
-            "\n[*] Cheapest version of deck '{deck}': {}",
-            currency.to_price(Some(total_price))
+            "[*] Still to buy: {} cards ({})",
+            to_buy_totals.0,
+            currency.to_price(Some(to_buy_totals.1))
+        );
+    }
+
+    if !unresolved.is_empty() {
+        println!("\n[*] Unresolved entries (not included in the total):");
+        for entry in unresolved {
+            println!("[*] {entry}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Copies of each card in the collection by normalised name, only counting the
+/// given finishes (or every finish when none are given)
+async fn owned_copies(db: &mut MageDeck, finishes: &[Finish]) -> Result<Vec<(String, u32)>> {
+    let mut owned: Vec<(String, u32)> = Vec::new();
+    let cards = db.get_collection().await.context("loading collection")?;
+    for card in cards {
+        if !finishes.is_empty() && !finishes.contains(&card.finish) {
+            continue;
+        }
+
+        let key = normalise_name(&card.name);
+        match owned.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, quantity)) => *quantity += card.quantity,
+            None => owned.push((key, card.quantity)),
+        }
+    }
+
+    Ok(owned)
+}
+
+/// Uses up to `quantity` owned copies of a card, returning how many were used
+fn take_owned(owned: &mut [(String, u32)], name: &str, quantity: u32) -> u32 {
+    let key = normalise_name(name);
+    let Some((_, available)) = owned.iter_mut().find(|(k, _)| *k == key) else {
+        return 0;
+    };
+
+    let taken = quantity.min(*available);
+    *available -= taken;
+    taken
+}
+
+pub(crate) async fn convert(
+    deck: String,
+    to: DeckFormat,
+    deck_format: Option<DeckFormat>,
+    output: Option<String>,
+) -> Result<()> {
+    let mut db = if get_project_dir()?.exists() {
+        Some(MageDeck::load().await.context("loading db")?)
+    } else {
+        None
+    };
+    let mut loaded_deck = load_deck(db.as_mut(), &deck, deck_format).await?;
+
+    // Fill in set codes, collector numbers and MTGO IDs for formats which need them
+    if let Some(db) = db.as_mut() {
+        let deck_cards = lookup_deck_cards(db, &loaded_deck).await?;
+        let lookups = deck_cards.cards.into_iter().zip(deck_cards.candidates);
+        for (entry, (card, candidates)) in loaded_deck.cards.iter_mut().zip(lookups) {
+            let Some(card) = card else {
+                if candidates.is_empty() {
+                    eprintln!("[*] No entry found for '{}'", entry.name);
+                } else {
+                    let candidates = format_candidates(&candidates);
+                    eprintln!("[*] '{}' is ambiguous, matching: {candidates}", entry.name);
+                }
+                continue;
+            };
+
+            if entry.set.is_none() {
+                entry.set = card.set.clone();
+            }
+
+            // Collector numbers and MTGO IDs only apply to the printing stored in the database
+            if entry.set != card.set {
+                continue;
+            }
+
+            if entry.collector_number.is_none() {
+                entry.collector_number = card.collector_number;
+            }
+
+            if entry.mtgo_id.is_none() {
+                entry.mtgo_id = card.mtgo_id;
+            }
+        }
+    } else {
+        eprintln!("[*] Project is not initialised, card details will not be filled in");
+    }
+
+    match output {
+        Some(output) => {
+            formats::save_deck(&loaded_deck, &output, Some(to))
+                .await
+                .context("saving converted deck")?;
+            println!("[*] Converted deck '{deck}' to {output}");
+        }
+        None => print!("{}", formats::write_deck(&loaded_deck, to)?),
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn check(
+    deck: String,
+    format: Format,
+    deck_format: Option<DeckFormat>,
+) -> Result<()> {
+    if !is_initialised()? {
+        return Ok(());
+    }
+
+    let mut db = MageDeck::load().await.context("loading db")?;
+    let loaded_deck = load_deck(Some(&mut db), &deck, deck_format).await?;
+    let DeckCards { cards, candidates } = lookup_deck_cards(&mut db, &loaded_deck).await?;
+
+    let violations = check_deck(&loaded_deck, &cards, &candidates, format);
+    if violations.is_empty() {
+        println!("[*] Deck '{deck}' is legal in {format}");
+        return Ok(());
+    }
+
+    for violation in violations.iter() {
+        println!("[*] {violation}");
+    }
+    let plural = if violations.len() == 1 { "" } else { "s" };
+    println!(
+        "\n[*] Deck '{deck}' is not legal in {format} ({} violation{plural})",
+        violations.len()
+    );
+
+    Ok(())
+}
+
+pub(crate) async fn formats(
+    deck: String,
+    all: bool,
+    deck_format: Option<DeckFormat>,
+) -> Result<()> {
+    if !is_initialised()? {
+        return Ok(());
+    }
+
+    let mut db = MageDeck::load().await.context("loading db")?;
+    let loaded_deck = load_deck(Some(&mut db), &deck, deck_format).await?;
+    let DeckCards { cards, candidates } = lookup_deck_cards(&mut db, &loaded_deck).await?;
+
+    let formats: Vec<Format> = if all {
+        Format::value_variants().to_vec()
+    } else {
+        Format::MAIN.to_vec()
+    };
+
+    let mut unknown = Vec::new();
+    let mut ambiguous = Vec::new();
+    let mut rows: Vec<&DbCard> = Vec::new();
+    let entries = loaded_deck
+        .cards
+        .iter()
+        .zip(cards.iter())
+        .zip(candidates.iter());
+    for ((entry, card), candidates) in entries {
+        match card {
+            Some(card) if !rows.iter().any(|row| row.name == card.name) => rows.push(card),
+            Some(_) => {}
+            None if candidates.is_empty() => unknown.push(entry.name.clone()),
+            None => ambiguous.push(format!(
+                "{} (matching: {})",
+                entry.name,
+                format_candidates(candidates)
+            )),
+        }
+    }
+
+    let name_width = rows
+        .iter()
+        .map(|card| card.name.as_deref().unwrap_or_default().chars().count())
+        .max()
+        .unwrap_or_default()
+        .max("Card".len());
+    let widths: Vec<usize> = formats
+        .iter()
+        .map(|format| format.key().len().max("restricted".len()))
+        .collect();
+
+    let mut header = format!("[*] {:<name_width$}", "Card");
+    for (format, width) in formats.iter().zip(widths.iter()) {
+        header.push_str(&format!("  {:<width$}", format.key()));
+    }
+    println!("{}", header.trim_end());
+
+    for card in rows {
+        let mut row = format!(
+            "[*] {:<name_width$}",
+            card.name.as_deref().unwrap_or_default()
         );
+        for (format, width) in formats.iter().zip(widths.iter()) {
+            row.push_str(&format!(
+                "  {:<width$}",
+                Legality::of(card, *format).to_string()
+            ));
+        }
+        println!("{}", row.trim_end());
+    }
+
+    let mut legal_in = Vec::new();
+    let mut deck_row = format!("[*] {:<name_width$}", "Deck");
+    for (format, width) in formats.iter().zip(widths.iter()) {
+        let legal = check_deck(&loaded_deck, &cards, &candidates, *format).is_empty();
+        if legal {
+            legal_in.push(format.to_string());
+        }
+
+        let legal = if legal { "yes" } else { "no" };
+        deck_row.push_str(&format!("  {legal:<width$}"));
+    }
+    println!("{}", deck_row.trim_end());
+
+    if !unknown.is_empty() {
+        println!("\n[*] Unknown cards: {}", unknown.join(", "));
+    }
+
+    if !ambiguous.is_empty() {
+        println!("\n[*] Ambiguous cards: {}", ambiguous.join(", "));
+    }
+
+    if legal_in.is_empty() {
+        println!("\n[*] Deck '{deck}' isn't legal in any of these formats");
+    } else {
+        println!("\n[*] Deck '{deck}' is legal in: {}", legal_in.join(", "));
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn stats(deck: String, json: bool, deck_format: Option<DeckFormat>) -> Result<()> {
+    if !is_initialised()? {
+        return Ok(());
+    }
+
+    let mut db = MageDeck::load().await.context("loading db")?;
+    let loaded_deck = load_deck(Some(&mut db), &deck, deck_format).await?;
+    let cards = lookup_deck_cards(&mut db, &loaded_deck).await?.cards;
+    let stats = DeckStats::new(&loaded_deck, &cards);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+
+    println!("[*] Stats for deck '{deck}'");
+    print!("{stats}");
+    if !stats.unknown.is_empty() {
         println!(
-            "[*] Cheapest card: {} {}",
-            cheapest.0,
-            currency.to_price(Some(cheapest.1))
+            "\n[*] Unknown cards (not included): {}",
+            stats.unknown.join(", ")
         );
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn mana(deck: String, json: bool, deck_format: Option<DeckFormat>) -> Result<()> {
+    if !is_initialised()? {
+        return Ok(());
+    }
+
+    let mut db = MageDeck::load().await.context("loading db")?;
+    let loaded_deck = load_deck(Some(&mut db), &deck, deck_format).await?;
+    let cards = lookup_deck_cards(&mut db, &loaded_deck).await?.cards;
+    let mana_base = ManaBase::new(&loaded_deck, &cards);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&mana_base)?);
+        return Ok(());
+    }
+
+    println!("[*] Mana base for deck '{deck}'");
+    print!("{mana_base}");
+    if !mana_base.unknown.is_empty() {
         println!(
-            "[*] Most expensive card: {} {}",
-            most_expensive.0,
-            currency.to_price(Some(most_expensive.1))
+            "\n[*] Unknown cards (not included): {}",
+            mana_base.unknown.join(", ")
         );
-        println!("[*] {}", currency.to_purchase_location());
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn diff(
+    old: String,
+    new: String,
+    currency: Currency,
+    include_basics: bool,
+    deck_format: Option<DeckFormat>,
+) -> Result<()> {
+    let mut db = if get_project_dir()?.exists() {
+        Some(MageDeck::load().await.context("loading db")?)
     } else {
-        println!("[*] Need either `--deck` or `--card` argument to be set!");
+        None
+    };
+    let old_deck = load_deck(db.as_mut(), &old, deck_format)
+        .await
+        .context("loading old deck")?;
+    let new_deck = load_deck(db.as_mut(), &new, deck_format)
+        .await
+        .context("loading new deck")?;
+
+    let diff = DeckDiff::new(&old_deck, &new_deck);
+    if diff.is_empty() {
+        println!("[*] No changes between '{old}' and '{new}'");
+        return Ok(());
+    }
+
+    for section in SECTIONS {
+        let mut changes = diff.section(section).peekable();
+        if changes.peek().is_none() {
+            continue;
+        }
+
+        println!("[*] {section}");
+        for change in changes {
+            println!("[*]   {change}");
+        }
+    }
+
+    let additions = diff.additions();
+    if additions.is_empty() {
+        println!("\n[*] Nothing new to buy");
+        return Ok(());
+    }
+
+    if !is_initialised()? {
+        return Ok(());
+    }
+
+    let mut db = match db {
+        Some(db) => db,
+        None => MageDeck::load().await.context("loading db")?,
+    };
+    let mut total_price = 0.0;
+    let mut unpriced = Vec::new();
+    println!("\n[*] Additions:");
+    for (name, quantity) in additions {
+        let entry = match db.resolve_name(&name, false).await? {
+            Resolution::Found(resolved) => db.get_cheapest_card(&resolved, currency).await?,
+            Resolution::Ambiguous(_) | Resolution::NotFound => None,
+        };
+
+        let Some(entry) = entry else {
+            unpriced.push(format!("{quantity}x {name}"));
+            continue;
+        };
+
+        if !include_basics && entry.is_basic_land() {
+            continue;
+        }
+
+        let Some(price) = entry.price else {
+            unpriced.push(format!("{quantity}x {name}"));
+            continue;
+        };
+
+        let price = price * quantity as f32;
+        total_price += price;
+        println!(
+            "[*] {quantity}x {} - {} ({}): {}",
+            entry.name.unwrap_or_default(),
+            entry.set_name.unwrap_or_default(),
+            entry.set_tag.unwrap_or_default().to_uppercase(),
+            currency.to_price(Some(price))
+        );
+    }
+
+    println!(
+        "\n[*] Upgrade cost: {}",
+        currency.to_price(Some(total_price))
+    );
+    if !unpriced.is_empty() {
+        println!("[*] Not priced: {}", unpriced.join(", "));
     }
 
     Ok(())
 }
 
-pub(crate) async fn get(card: String) -> Result<()> {
+pub(crate) async fn simulate(deck: String, options: SimulateOptions) -> Result<()> {
     if !is_initialised()? {
         return Ok(());
     }
 
-    let mut db = MageDeck::load().await?;
-    let card = sanitise(&card);
-    let cards = db.get_cards(&card).await?;
-    if cards.is_empty() {
-        println!("[*] No card matching '{card}'");
+    let mut db = MageDeck::load().await.context("loading db")?;
+    let loaded_deck = load_deck(Some(&mut db), &deck, options.deck_format).await?;
+    let cards = lookup_deck_cards(&mut db, &loaded_deck).await?.cards;
+
+    // Only the main deck is shuffled, copies split over several lines are grouped
+    let mut library: Vec<(String, u32, bool)> = Vec::new();
+    for (entry, card) in loaded_deck.cards.iter().zip(cards.iter()) {
+        if entry.section != DeckSection::Main {
+            continue;
+        }
+
+        let name = card
+            .as_ref()
+            .and_then(|card| card.name.clone())
+            .unwrap_or_else(|| entry.name.clone());
+        let is_land = card.as_ref().is_some_and(|card| card.has_type("Land"));
+        match library.iter_mut().find(|(n, _, _)| *n == name) {
+            Some((_, copies, _)) => *copies += entry.quantity,
+            None => library.push((name, entry.quantity, is_land)),
+        }
     }
 
-    for card in cards {
-        println!("{card}");
+    let seed = options.seed.unwrap_or_else(rand::random);
+    let grouped: Vec<(usize, u32, bool)> = library
+        .iter()
+        .enumerate()
+        .map(|(i, (_, copies, is_land))| (i, *copies, *is_land))
+        .collect();
+    let mut simulator = Simulator::new(&grouped, seed);
+
+    let mut tracked = Vec::new();
+    for card in options.cards.iter() {
+        let name = normalise_name(card);
+        match library
+            .iter()
+            .position(|(n, _, _)| normalise_name(n) == name)
+        {
+            Some(index) => tracked.push(index),
+            None => println!("[*] '{card}' isn't in the main deck"),
+        }
+    }
+
+    let draw_step = if options.play { 0 } else { 1 };
+    let cards_seen = 7 + options.turn.saturating_sub(1) as usize + draw_step;
+    let keep = KeepRange {
+        min_lands: options.min_lands,
+        max_lands: options.max_lands,
+    };
+    let result = simulator.run(options.trials, keep, &tracked, cards_seen);
+    let percent = |rate: f64| format!("{:.1}%", rate * 100.0);
+
+    println!(
+        "[*] Simulated {} shuffles of deck '{deck}' ({} cards, seed {seed})",
+        options.trials,
+        simulator.library_size()
+    );
+    println!(
+        "[*] At least {} lands in opening 7: {}",
+        options.lands,
+        percent(result.at_least_lands(options.lands))
+    );
+
+    let distribution: Vec<String> = result
+        .opening_lands
+        .iter()
+        .enumerate()
+        .map(|(lands, count)| format!("{lands}: {}", percent(result.rate(*count))))
+        .collect();
+    println!("[*] Lands in opening 7: {}", distribution.join(", "));
+
+    println!(
+        "[*] London mulligan keeping {}-{} lands: keep 7 {}, keep 6 {}, keep 5 {}",
+        options.min_lands,
+        options.max_lands,
+        percent(result.rate(result.mulligans[0])),
+        percent(result.rate(result.mulligans[1])),
+        percent(result.rate(result.mulligans[2]))
+    );
+
+    let play = if options.play { "play" } else { "draw" };
+    for (index, seen) in tracked.iter().zip(result.seen_by_turn.iter()) {
+        let (name, copies, _) = &library[*index];
+        let exact = hypergeometric_at_least(
+            simulator.library_size() as u32,
+            *copies,
+            cards_seen as u32,
+            1,
+        );
+        println!(
+            "[*] {name} ({copies} copies) by turn {} on the {play}: {} (exact {})",
+            options.turn,
+            percent(result.rate(*seen)),
+            percent(exact)
+        );
+    }
+
+    Ok(())
+}
+
+pub(crate) fn hypergeometric(deck_size: u32, copies: u32, draws: u32, hits: u32) -> Result<()> {
+    if copies > deck_size || draws > deck_size {
+        anyhow::bail!("copies and draws can't be more than the deck size");
+    }
+
+    let exactly = crate::simulate::hypergeometric(deck_size, copies, draws, hits);
+    let at_least = hypergeometric_at_least(deck_size, copies, draws, hits);
+    let at_most = 1.0 - at_least + exactly;
+
+    println!("[*] Drawing {draws} from {deck_size} cards with {copies} copies:");
+    println!("[*] Exactly {hits}: {:.2}%", exactly * 100.0);
+    println!("[*] At least {hits}: {:.2}%", at_least * 100.0);
+    println!("[*] At most {hits}: {:.2}%", at_most * 100.0);
+
+    Ok(())
+}
+
+pub(crate) async fn collection(command: CollectionCommands) -> Result<()> {
+    if !is_initialised()? {
+        return Ok(());
+    }
+
+    let mut db = MageDeck::load().await.context("loading db")?;
+    match command {
+        CollectionCommands::Add { entry } => collection_add(&mut db, entry).await,
+        CollectionCommands::Remove { entry } => collection_remove(&mut db, entry).await,
+        CollectionCommands::List { filter } => collection_list(&mut db, filter).await,
+        CollectionCommands::Value { currency } => collection_value(&mut db, currency).await,
+        CollectionCommands::Import {
+            file,
+            from,
+            dry_run,
+        } => collection_import(&mut db, file, from, dry_run).await,
+    }
+}
+
+/// Resolves the card named by a collection entry, printing why when it can't be
+async fn resolve_collection_entry(
+    db: &mut MageDeck,
+    entry: CollectionEntry,
+) -> Result<Option<CollectionCard>> {
+    let Some(resolved) = resolve_card_name(db, &entry.card, false).await? else {
+        return Ok(None);
+    };
+
+    let mut card = CollectionCard::new(resolved, entry.quantity);
+    card.set = entry.set.unwrap_or_default().to_uppercase();
+    card.collector_number = entry.collector_number.unwrap_or_default();
+    card.finish = entry.finish;
+    card.condition = entry.condition;
+    card.language = entry.language.to_lowercase();
+
+    Ok(Some(card))
+}
+
+async fn collection_add(db: &mut MageDeck, entry: CollectionEntry) -> Result<()> {
+    let Some(mut card) = resolve_collection_entry(db, entry).await? else {
+        return Ok(());
+    };
+
+    // Default to the printing from the last sync when no set is given
+    if card.set.is_empty() {
+        if let Some(synced) = db.get_card(&card.name).await? {
+            card.set = synced.set.unwrap_or_default();
+            if card.collector_number.is_empty() {
+                card.collector_number = synced.collector_number.unwrap_or_default();
+            }
+        }
+    }
+
+    db.add_to_collection(&card)
+        .await
+        .context("adding card to collection")?;
+    println!("[*] Added {card}");
+
+    Ok(())
+}
+
+async fn collection_remove(db: &mut MageDeck, entry: CollectionEntry) -> Result<()> {
+    let Some(card) = resolve_collection_entry(db, entry).await? else {
+        return Ok(());
+    };
+
+    let removed = db
+        .remove_from_collection(&card)
+        .await
+        .context("removing card from collection")?;
+
+    match removed {
+        0 => println!(
+            "[*] No matching copies of '{}' in the collection",
+            card.name
+        ),
+        removed if removed < card.quantity => println!(
+            "[*] Removed {removed}x {} ({} requested, only {removed} in the collection)",
+            card.name, card.quantity
+        ),
+        removed => println!("[*] Removed {removed}x {}", card.name),
+    }
+
+    Ok(())
+}
+
+async fn collection_list(db: &mut MageDeck, filter: Option<String>) -> Result<()> {
+    let filter = filter.as_deref().map(normalise_name);
+    let cards: Vec<CollectionCard> = db
+        .get_collection()
+        .await?
+        .into_iter()
+        .filter(|card| match &filter {
+            Some(filter) => normalise_name(&card.name).contains(filter),
+            None => true,
+        })
+        .collect();
+
+    if cards.is_empty() {
+        println!("[*] No cards in the collection");
+        return Ok(());
+    }
+
+    for card in cards.iter() {
+        println!("[*] {card}");
+    }
+
+    let total: u32 = cards.iter().map(|card| card.quantity).sum();
+    println!("\n[*] {total} cards ({} entries)", cards.len());
+
+    Ok(())
+}
+
+async fn collection_value(db: &mut MageDeck, currency: Currency) -> Result<()> {
+    let cards = db.get_collection().await?;
+    if cards.is_empty() {
+        println!("[*] No cards in the collection");
+        return Ok(());
+    }
+
+    let mut total_price = 0.0;
+    let mut unpriced = Vec::new();
+    for card in cards.iter() {
+        let finish_currency = card.finish.currency(currency);
+
+        // Copies of the synced printing are priced by it, others by the cheapest printing
+        let printing = if card.set.is_empty() || card.collector_number.is_empty() {
+            None
+        } else {
+            db.get_printing(&card.set, &card.collector_number).await?
+        };
+
+        let printing_price = printing
+            .and_then(|printing| printing.price(finish_currency).or(printing.price(currency)));
+        let price = match printing_price {
+            Some(price) => Some(price),
+            None => cheapest_price(db, &card.name, finish_currency, currency).await?,
+        };
+
+        let Some(price) = price else {
+            unpriced.push(card.to_string());
+            continue;
+        };
+
+        let price = price * card.quantity as f32;
+        total_price += price;
+        println!("[*] {card}: {}", currency.to_price(Some(price)));
+    }
+
+    println!(
+        "\n[*] Collection value: {}",
+        currency.to_price(Some(total_price))
+    );
+
+    if !unpriced.is_empty() {
+        println!("\n[*] No price found (not included in the total):");
+        for card in unpriced {
+            println!("[*] {card}");
+        }
+    }
+
+    Ok(())
+}
+
+async fn collection_import(
+    db: &mut MageDeck,
+    file: String,
+    app: ScannerApp,
+    dry_run: bool,
+) -> Result<()> {
+    let content = tokio::fs::read_to_string(&file)
+        .await
+        .context("loading collection export")?;
+    let rows = scanner::parse(&content, app)?;
+
+    let mut by_scryfall_id = 0;
+    let mut by_printing = 0;
+    let mut by_name = 0;
+    let mut imported = 0;
+    let mut unmatched = Vec::new();
+    for row in rows.iter() {
+        let Some((synced, method)) = match_scanned_card(db, row).await? else {
+            unmatched.push(row);
+            continue;
+        };
+
+        let mut card = CollectionCard::new(synced.name.unwrap_or_default(), row.quantity);
+        card.set = row.set.clone();
+        card.collector_number = row.collector_number.clone();
+        if card.set.is_empty() {
+            card.set = synced.set.unwrap_or_default();
+            card.collector_number = synced.collector_number.unwrap_or_default();
+        }
+        card.finish = row.finish;
+        card.condition = row.condition;
+        card.language = row.language.clone();
+
+        if dry_run {
+            let matched_by = match method {
+                MatchMethod::ScryfallId => "by Scryfall id",
+                MatchMethod::Printing => "by set and collector number",
+                // Only one printing of each card is synced, so most printings in
+                // an export are only matched by name
+                MatchMethod::Name if !row.set.is_empty() => "by name, printing not in the database",
+                MatchMethod::Name => "by name",
+            };
+            println!("[*] Would add {card} (matched {matched_by})");
+        } else {
+            db.add_to_collection(&card)
+                .await
+                .context("adding card to collection")?;
+        }
+
+        match method {
+            MatchMethod::ScryfallId => by_scryfall_id += 1,
+            MatchMethod::Printing => by_printing += 1,
+            MatchMethod::Name => by_name += 1,
+        }
+        imported += card.quantity;
+    }
+
+    let rows_matched = rows.len() - unmatched.len();
+    let summary = format!(
+        "{imported} cards from {file} ({rows_matched} of {} rows matched)",
+        rows.len()
+    );
+    if dry_run {
+        println!("\n[*] Would import {summary}, the collection is unchanged (dry run)");
+    } else {
+        println!("[*] Imported {summary}");
+    }
+
+    println!(
+        "[*] Matched {by_scryfall_id} by Scryfall id, {by_printing} by set and collector number, {by_name} by name"
+    );
+
+    if !unmatched.is_empty() {
+        let plural = if unmatched.len() == 1 { "" } else { "s" };
+        println!(
+            "\n[*] {} row{plural} couldn't be matched to a card:",
+            unmatched.len()
+        );
+        for row in unmatched {
+            println!("[*] {row}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds the card for a row of a scanner export by its Scryfall id, then its set
+/// and collector number, then its exact name. Returns the card with the method
+/// that matched. Sync only stores one printing of each card, so rows
+/// for other printings fall through to the name
+async fn match_scanned_card(
+    db: &mut MageDeck,
+    row: &ScannedCard,
+) -> Result<Option<(DbCard, MatchMethod)>> {
+    if let Some(id) = &row.scryfall_id {
+        if let Some(card) = db.get_card_by_scryfall_id(id).await? {
+            return Ok(Some((card, MatchMethod::ScryfallId)));
+        }
+    }
+
+    // Apps don't all use Scryfall's set codes, so only trust a printing match
+    // when the name agrees
+    if !row.set.is_empty() && !row.collector_number.is_empty() {
+        let name = normalise_name(&row.name);
+        if let Some(card) = db.get_printing(&row.set, &row.collector_number).await? {
+            let synced = normalise_name(card.name.as_deref().unwrap_or_default());
+            if synced == name || synced.split(" // ").any(|face| face == name) {
+                return Ok(Some((card, MatchMethod::Printing)));
+            }
+        }
+    }
+
+    let card = match db.resolve_name(&row.name, true).await? {
+        Resolution::Found(name) => db.get_card(&name).await?,
+        Resolution::Ambiguous(_) | Resolution::NotFound => None,
+    };
+
+    Ok(card.map(|card| (card, MatchMethod::Name)))
+}
+
+/// Cheapest price of a card in a currency, falling back to another currency
+/// (e.g. the nonfoil price for a foil) when there isn't one
+async fn cheapest_price(
+    db: &mut MageDeck,
+    name: &str,
+    currency: Currency,
+    fallback: Currency,
+) -> Result<Option<f32>> {
+    let price = db
+        .get_cheapest_card(name, currency)
+        .await?
+        .and_then(|entry| entry.price);
+
+    if price.is_some() || currency == fallback {
+        return Ok(price);
+    }
+
+    let price = db
+        .get_cheapest_card(name, fallback)
+        .await?
+        .and_then(|entry| entry.price);
+    Ok(price)
+}
+
+pub(crate) async fn plan(
+    decks: Vec<String>,
+    currency: Currency,
+    include_basics: bool,
+    deck_format: Option<DeckFormat>,
+) -> Result<()> {
+    if !is_initialised()? {
+        return Ok(());
+    }
+
+    if decks.len() > MAX_DECKS {
+        anyhow::bail!("can only plan up to {MAX_DECKS} decks at once");
+    }
+
+    let mut db = MageDeck::load().await.context("loading db")?;
+    let mut needs = Vec::with_capacity(decks.len());
+    for path in decks.iter() {
+        let deck = load_deck(Some(&mut db), path, deck_format)
+            .await
+            .with_context(|| format!("loading deck '{path}'"))?;
+        let cards = lookup_deck_cards(&mut db, &deck).await?.cards;
+
+        // Cards that can't be found are planned under the name in the deck
+        let mut deck_needs = Vec::new();
+        for (entry, card) in deck.cards.iter().zip(cards) {
+            if !include_basics && card.as_ref().is_some_and(DbCard::is_basic_land) {
+                continue;
+            }
+
+            let name = card
+                .and_then(|card| card.name)
+                .unwrap_or_else(|| entry.name.clone());
+            deck_needs.push((name, entry.quantity));
+        }
+
+        needs.push((deck.name.unwrap_or_else(|| path.clone()), deck_needs));
+    }
+
+    let owned = owned_copies(&mut db, &[]).await?;
+    let plan = BuildPlan::new(needs, &owned);
+    for (index, deck) in plan.decks.iter().enumerate() {
+        match plan.missing(index) {
+            0 => println!("[*] {deck}: can be built from the collection"),
+            missing => println!("[*] {deck}: missing {missing} cards"),
+        }
+    }
+
+    let deck_names = |group: &[usize]| {
+        group
+            .iter()
+            .map(|deck| plan.decks[*deck].as_str())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    };
+
+    let all_decks: Vec<usize> = (0..plan.decks.len()).collect();
+    let groups = plan.largest_buildable();
+    if plan.can_build(&all_decks) {
+        println!("\n[*] Every deck can be built at the same time");
+    } else if groups.is_empty() {
+        println!("\n[*] None of the decks can be built from the collection");
+    } else {
+        println!("\n[*] Largest groups of decks that can be built at the same time:");
+        for group in groups {
+            println!("[*]   {}", deck_names(&group));
+        }
+    }
+
+    let mut conflicts = plan.conflicts().peekable();
+    if conflicts.peek().is_some() {
+        println!("\n[*] Conflicts:");
+        for card in conflicts {
+            let needed_by: Vec<String> = card
+                .needed
+                .iter()
+                .zip(plan.decks.iter())
+                .filter(|(needed, _)| **needed > 0)
+                .map(|(needed, deck)| format!("{deck} {needed}"))
+                .collect();
+
+            println!(
+                "[*]   {}: {} owned, {} needed ({})",
+                card.name,
+                card.owned,
+                card.total(),
+                needed_by.join(", ")
+            );
+        }
+    }
+
+    let mut shopping_list = plan.shopping_list().peekable();
+    if shopping_list.peek().is_none() {
+        return Ok(());
+    }
+
+    let mut total_price = 0.0;
+    let mut unpriced = Vec::new();
+    println!("\n[*] Shopping list to build every deck at the same time:");
+    for card in shopping_list {
+        let quantity = card.shortfall();
+        let Some(entry) = db.get_cheapest_card(&card.name, currency).await? else {
+            unpriced.push(format!("{quantity}x {}", card.name));
+            continue;
+        };
+
+        let Some(price) = entry.price else {
+            unpriced.push(format!("{quantity}x {}", card.name));
+            continue;
+        };
+
+        let price = price * quantity as f32;
+        total_price += price;
+        println!(
+            "[*] {quantity}x {} - {} ({}): {}",
+            card.name,
+            entry.set_name.unwrap_or_default(),
+            entry.set_tag.unwrap_or_default().to_uppercase(),
+            currency.to_price(Some(price))
+        );
+    }
+
+    println!(
+        "\n[*] Shopping list total: {}",
+        currency.to_price(Some(total_price))
+    );
+    println!("[*] {}", currency.to_purchase_location());
+
+    if !unpriced.is_empty() {
+        println!("\n[*] Not priced (not included in the total):");
+        for entry in unpriced {
+            println!("[*] {entry}");
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn ledger(command: LedgerCommands) -> Result<()> {
+    if !is_initialised()? {
+        return Ok(());
+    }
+
+    let mut db = MageDeck::load().await.context("loading db")?;
+    match command {
+        LedgerCommands::Add { record, paid } => {
+            ledger_record(&mut db, record, Transaction::Purchase, paid).await
+        }
+        LedgerCommands::Sell { record, received } => {
+            ledger_record(&mut db, record, Transaction::Sale, received).await
+        }
+        LedgerCommands::List => ledger_list(&mut db).await,
+        LedgerCommands::Remove { id } => {
+            match db.remove_from_ledger(id).await? {
+                true => println!("[*] Removed ledger entry #{id}"),
+                false => println!("[*] No ledger entry #{id}"),
+            }
+            Ok(())
+        }
+        LedgerCommands::Report { currency } => ledger_report(&mut db, currency).await,
+    }
+}
+
+async fn ledger_record(
+    db: &mut MageDeck,
+    record: LedgerRecord,
+    kind: Transaction,
+    amount: f32,
+) -> Result<()> {
+    let Some(name) = resolve_card_name(db, &record.card, false).await? else {
+        return Ok(());
+    };
+
+    let id = db
+        .add_to_ledger(
+            &name,
+            kind,
+            record.quantity,
+            amount,
+            record.currency,
+            record.date.as_deref(),
+        )
+        .await
+        .context("adding ledger entry")?;
+
+    let action = match kind {
+        Transaction::Purchase => "purchase",
+        Transaction::Sale => "sale",
+    };
+    println!(
+        "[*] Recorded {action} #{id}: {}x {name} for {}",
+        record.quantity,
+        record.currency.to_price(Some(amount))
+    );
+
+    Ok(())
+}
+
+async fn ledger_list(db: &mut MageDeck) -> Result<()> {
+    let entries = db.get_ledger().await?;
+    if entries.is_empty() {
+        println!("[*] No purchases or sales recorded");
+        return Ok(());
+    }
+
+    for entry in entries {
+        println!("[*] {entry}");
+    }
+
+    Ok(())
+}
+
+async fn ledger_report(db: &mut MageDeck, currency: Currency) -> Result<()> {
+    let currency = currency.base();
+    let entries = db.get_ledger().await?;
+    let holdings = ledger::holdings(&entries, currency);
+    if holdings.is_empty() {
+        println!("[*] No purchases or sales recorded in this currency");
+        return Ok(());
+    }
+
+    let mut held_cost = 0.0;
+    let mut market_value = 0.0;
+    let mut realised = 0.0;
+    let mut unpriced = Vec::new();
+    let mut oversold = Vec::new();
+    for holding in holdings.iter() {
+        realised += holding.realised();
+        if holding.oversold() > 0 {
+            oversold.push(holding);
+        }
+
+        let mut line = format!(
+            "[*] {}: {} bought for {}",
+            holding.name,
+            holding.bought,
+            holding.currency.to_price(Some(holding.cost))
+        );
+
+        if holding.sold > 0 {
+            line.push_str(&format!(
+                ", {} sold for {} ({} realised)",
+                holding.sold,
+                holding.currency.to_price(Some(holding.proceeds)),
+                format_gain(currency, holding.realised())
+            ));
+        }
+
+        let held = holding.held();
+        if held > 0 {
+            let price = cheapest_price(db, &holding.name, holding.currency, currency).await?;
+            match price {
+                Some(price) => {
+                    let value = price * held as f32;
+                    held_cost += holding.cost_of_held();
+                    market_value += value;
+                    line.push_str(&format!(
+                        ", {held} held worth {} ({})",
+                        holding.currency.to_price(Some(value)),
+                        format_gain(currency, value - holding.cost_of_held())
+                    ));
+                }
+                None => {
+                    line.push_str(&format!(", {held} held"));
+                    unpriced.push(holding.name.clone());
+                }
+            }
+        }
+
+        println!("{line}");
+    }
+
+    let unrealised = market_value - held_cost;
+    println!(
+        "\n[*] Cards held cost {} and are worth {} ({})",
+        currency.to_price(Some(held_cost)),
+        currency.to_price(Some(market_value)),
+        format_gain(currency, unrealised)
+    );
+    println!(
+        "[*] Realised from sales: {}",
+        format_gain(currency, realised)
+    );
+    println!(
+        "[*] Overall profit / loss: {}",
+        format_gain(currency, unrealised + realised)
+    );
+
+    if !unpriced.is_empty() {
+        println!("\n[*] No market price found (held copies not included in the totals):");
+        for name in unpriced {
+            println!("[*] {name}");
+        }
+    }
+
+    if !oversold.is_empty() {
+        println!("\n[*] More copies sold than recorded as bought (extra sales not included in the realised gains):");
+        for holding in oversold {
+            println!(
+                "[*] {}: {} sold, {} bought",
+                holding.name, holding.sold, holding.bought
+            );
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn deck(command: DeckCommands) -> Result<()> {
+    if !is_initialised()? {
+        return Ok(());
+    }
+
+    let mut db = MageDeck::load().await.context("loading db")?;
+    match command {
+        DeckCommands::Save {
+            file,
+            name,
+            deck_format,
+        } => deck_save(&mut db, file, name, deck_format).await,
+        DeckCommands::List => deck_list(&mut db).await,
+        DeckCommands::Show { name } => deck_show(&mut db, &name).await,
+        DeckCommands::Value { name, currency } => {
+            deck_value_history(&mut db, &name, currency).await
+        }
+        DeckCommands::Remove { name } => {
+            let (deck, revision) = saved::parse_reference(&name);
+            match db.remove_saved_deck(deck, revision).await? {
+                true => println!("[*] Removed saved deck '{name}'"),
+                false => println!("[*] No saved deck '{name}'"),
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn deck_save(
+    db: &mut MageDeck,
+    file: String,
+    name: Option<String>,
+    deck_format: Option<DeckFormat>,
+) -> Result<()> {
+    let deck = formats::load_deck(&file, deck_format).await?;
+    let Some(name) = name.or_else(|| deck.name.clone()) else {
+        anyhow::bail!("no name given for deck '{file}'");
+    };
+
+    if name.contains('@') {
+        anyhow::bail!("saved deck names can't contain '@', which is used for revisions");
+    }
+
+    let changes = match db.get_saved_deck(&name, None).await? {
+        Some((revision, previous)) => {
+            let diff = DeckDiff::new(&previous, &deck);
+            if diff.is_empty() {
+                println!("[*] '{name}' is unchanged since revision {revision}");
+                return Ok(());
+            }
+
+            Some((revision, diff.changes.len()))
+        }
+        None => None,
+    };
+
+    let revision = db.save_deck(&name, &deck).await.context("saving deck")?;
+    let cards: u32 = deck.cards.iter().map(|card| card.quantity).sum();
+    match changes {
+        Some((previous, changes)) => println!(
+            "[*] Saved '{name}' revision {revision} ({cards} cards, {changes} changes from revision {previous})"
+        ),
+        None => println!("[*] Saved '{name}' ({cards} cards)"),
+    }
+
+    Ok(())
+}
+
+async fn deck_list(db: &mut MageDeck) -> Result<()> {
+    let decks = db.get_saved_decks().await?;
+    if decks.is_empty() {
+        println!("[*] No saved decks");
+        return Ok(());
+    }
+
+    for deck in decks {
+        println!("[*] {deck}");
+    }
+
+    Ok(())
+}
+
+async fn deck_show(db: &mut MageDeck, reference: &str) -> Result<()> {
+    let (name, revision) = saved::parse_reference(reference);
+    let Some((revision, deck)) = db.get_saved_deck(name, revision).await? else {
+        println!("[*] No saved deck '{reference}'");
+        return Ok(());
+    };
+
+    let name = deck.name.clone().unwrap_or_default();
+    println!("[*] {name} revision {revision}\n");
+    print!("{}", formats::write_deck(&deck, DeckFormat::Text)?);
+
+    println!("\n[*] Revisions:");
+    for saved in db.get_deck_revisions(&name).await? {
+        println!("[*]   {saved}");
+    }
+
+    Ok(())
+}
+
+async fn deck_value_history(db: &mut MageDeck, name: &str, currency: Currency) -> Result<()> {
+    let currency = currency.base();
+    let values = db.get_deck_values(name, currency).await?;
+    let (Some(first), Some(last)) = (values.first(), values.last()) else {
+        println!("[*] No values recorded for '{name}' yet, they're recorded on every sync");
+        return Ok(());
+    };
+
+    let mut previous: Option<f32> = None;
+    for value in values.iter() {
+        let mut line = format!(
+            "[*] {} (revision {}): {}",
+            value.recorded_at,
+            value.revision,
+            currency.to_price(Some(value.value))
+        );
+
+        if let Some(change) = previous.and_then(|previous| percent_change(previous, value.value)) {
+            line.push_str(&format!(" ({})", format_percent(change)));
+        }
+
+        if value.unpriced > 0 {
+            let plural = if value.unpriced == 1 { "" } else { "s" };
+            line.push_str(&format!(
+                ", {} card{plural} without a price",
+                value.unpriced
+            ));
+        }
+
+        println!("{line}");
+        previous = Some(value.value);
+    }
+
+    let change = percent_change(first.value, last.value)
+        .map(|change| format!(" ({})", format_percent(change)))
+        .unwrap_or_default();
+    println!(
+        "\n[*] Change since {}: {}{change}",
+        first.recorded_at,
+        format_gain(currency, last.value - first.value)
+    );
+
+    Ok(())
+}
+
+/// Value of a deck at the cheapest printing of each card, leaving out basic lands
+/// like `price --deck`. Returns the value with the copies of cards left out for
+/// having no price or not being found
+async fn deck_value(db: &mut MageDeck, deck: &Deck, currency: Currency) -> Result<(f32, u32)> {
+    let mut total = 0.0;
+    let mut unpriced = 0;
+    for entry in deck.cards.iter() {
+        let card = match db.resolve_name(&entry.name, false).await? {
+            Resolution::Found(name) => db.get_cheapest_card(&name, currency).await?,
+            Resolution::Ambiguous(_) | Resolution::NotFound => None,
+        };
+
+        let Some(card) = card else {
+            unpriced += entry.quantity;
+            continue;
+        };
+
+        if card.is_basic_land() {
+            continue;
+        }
+
+        match card.price {
+            Some(price) => total += price * entry.quantity as f32,
+            None => unpriced += entry.quantity,
+        }
+    }
+
+    Ok((total, unpriced))
+}
+
+fn format_percent(change: f32) -> String {
+    format!("{change:+.1}%")
+}
+
+/// Loads a deck from a file, or from the saved decks when there's no file with
+/// that name (`name@revision` loads an earlier revision). Only files can be
+/// loaded without a database
+async fn load_deck(
+    db: Option<&mut MageDeck>,
+    reference: &str,
+    format: Option<DeckFormat>,
+) -> Result<Deck> {
+    let Some(db) = db.filter(|_| !Path::new(reference).exists()) else {
+        return formats::load_deck(reference, format).await;
+    };
+
+    let (name, revision) = saved::parse_reference(reference);
+    match db.get_saved_deck(name, revision).await? {
+        Some((_, deck)) => Ok(deck),
+        None => anyhow::bail!("no deck file or saved deck '{reference}'"),
+    }
+}
+
+/// Resolves a card name to a single card, printing why when it can't be
+async fn resolve_card_name(
+    db: &mut MageDeck,
+    name: &str,
+    exact_match: bool,
+) -> Result<Option<String>> {
+    match db.resolve_name(name, exact_match).await? {
+        Resolution::Found(resolved) => Ok(Some(resolved)),
+        Resolution::Ambiguous(candidates) => {
+            let candidates = format_candidates(&candidates);
+            println!("[*] '{name}' is ambiguous, matching: {candidates}");
+            Ok(None)
+        }
+        Resolution::NotFound => {
+            let matcher = FuzzyMatcher::new(db.get_card_names().await?);
+            let suggestions = format_suggestions(&matcher.suggestions(name));
+            println!("[*] No entry found for '{name}'{suggestions}");
+            Ok(None)
+        }
+    }
+}
+
+/// Database entries for the cards of a deck, in deck order
+struct DeckCards {
+    /// Entry for each card, `None` for names that can't be resolved to a single card
+    cards: Vec<Option<DbCard>>,
+    /// Cards each name could be, empty unless the name matches several cards
+    candidates: Vec<Vec<String>>,
+}
+
+/// Looks up the database entry for every card in a deck
+async fn lookup_deck_cards(db: &mut MageDeck, deck: &Deck) -> Result<DeckCards> {
+    let mut cards = Vec::with_capacity(deck.cards.len());
+    let mut candidates = Vec::with_capacity(deck.cards.len());
+    for entry in deck.cards.iter() {
+        match db.resolve_name(&entry.name, false).await? {
+            Resolution::Found(name) => {
+                cards.push(db.get_card(&name).await?);
+                candidates.push(Vec::new());
+            }
+            Resolution::Ambiguous(names) => {
+                cards.push(None);
+                candidates.push(names);
+            }
+            Resolution::NotFound => {
+                cards.push(None);
+                candidates.push(Vec::new());
+            }
+        }
+    }
+
+    Ok(DeckCards { cards, candidates })
+}
+
+pub(crate) async fn get(card: String) -> Result<()> {
+    if !is_initialised()? {
+        return Ok(());
+    }
+
+    let mut db = MageDeck::load().await?;
+    let cards = db.get_cards(&card).await?;
+    if cards.is_empty() {
+        let matcher = FuzzyMatcher::new(db.get_card_names().await?);
+        let suggestions = format_suggestions(&matcher.suggestions(&card));
+        println!("[*] No card matching '{card}'{suggestions}");
+    }
+
+    for card in cards {
+        println!("{card}");
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn search(
+    query: String,
+    card_type: Option<String>,
+    currency: Currency,
+    limit: u32,
+    sort: SortOrder,
+    descending: bool,
+) -> Result<()> {
+    if !is_initialised()? {
+        return Ok(());
+    }
+
+    let mut search = SearchQuery::parse(&query).context("parsing search query")?;
+    if let Some(card_type) = card_type {
+        search = search.with_type(&card_type);
+    }
+
+    let mut db = MageDeck::load().await?;
+    let cards = db
+        .search(&search, currency, sort, descending, limit)
+        .await?;
+
+    if cards.is_empty() {
+        println!("[*] No cards matching '{query}'");
+    }
+
+    for card in cards {
+        println!(
+            "[*] {card} - {}",
+            card.type_line.as_deref().unwrap_or_default()
+        );
     }
 
     Ok(())
@@ -1,7 +1,17 @@
 pub(crate) mod commands;
 
-use crate::card::Currency;
-use clap::{Parser, Subcommand};
+use crate::{
+    card::Currency,
+    collection::{Condition, Finish},
+    formats::DeckFormat,
+    ledger::parse_date,
+    legality::Format,
+    query::SortOrder,
+    saved::VALUE_CHANGE_THRESHOLD,
+    scanner::ScannerApp,
+    utils::parse_non_negative,
+};
+use clap::{Args, Parser, Subcommand};
 
 #[derive(Debug, Parser)]
 #[command(version, about, long_about = None)]
@@ -16,30 +26,465 @@ pub(crate) enum Commands {
     Init,
 
     /// Synchronise card data with the latest info from Scryfall
-    Sync,
+    Sync {
+        /// List saved decks whose value changed by more than this percentage
+        #[arg(long, default_value_t = VALUE_CHANGE_THRESHOLD, value_parser = parse_non_negative)]
+        threshold: f32,
+
+        /// Currency to compare saved deck values in
+        #[arg(long, value_enum, default_value_t = Currency::Euro)]
+        currency: Currency,
+    },
 
     /// Get a card from the database
     Get { card: String },
 
+    /// Searches for cards using a Scryfall style query
+    /// (e.g. `c:r t:instant cmc<=2 usd<0.5 f:pauper set:mh3 "draw a card"`)
+    Search {
+        /// Search query, free text is matched against names, type lines and oracle text
+        #[arg(allow_hyphen_values = true)]
+        query: String,
+
+        /// Only include cards with this type (e.g. instant, creature)
+        #[arg(short, long = "type")]
+        card_type: Option<String>,
+
+        /// Currency format to use
+        #[arg(long, value_enum, default_value_t = Currency::Euro)]
+        currency: Currency,
+
+        /// Maximum number of results
+        #[arg(short, long, default_value_t = 20)]
+        limit: u32,
+
+        /// Order to list results in
+        #[arg(short, long, value_enum, default_value_t = SortOrder::Relevance)]
+        sort: SortOrder,
+
+        /// Reverse the sort order
+        #[arg(long)]
+        desc: bool,
+    },
+
     /// Gets the cheapest price for a card / deck with the given currency
     Price {
         /// Individual card to price
         #[arg(short, long, conflicts_with = "deck")]
         card: Option<String>,
 
-        /// Deck file to price
+        /// Deck file or saved deck to price
         #[arg(short, long, conflicts_with = "card")]
         deck: Option<String>,
 
+        /// Deck file format (guessed from the file extension if not set)
+        #[arg(long, value_enum, requires = "deck")]
+        deck_format: Option<DeckFormat>,
+
+        #[command(flatten)]
+        options: PriceOptions,
+    },
+
+    /// Converts a deck file between deck formats
+    Convert {
+        /// Deck file or saved deck to convert
+        deck: String,
+
+        /// Deck format to convert to
+        #[arg(short, long, value_enum)]
+        to: DeckFormat,
+
+        /// Deck file format to convert from (guessed from the file extension if not set)
+        #[arg(long, value_enum)]
+        deck_format: Option<DeckFormat>,
+
+        /// Output file to write the converted deck to (prints to stdout if not set)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Checks a deck is legal in a format (card legality, deck size, copy limits and
+    /// commander rules)
+    Check {
+        /// Deck file or saved deck to check
+        deck: String,
+
+        /// Format to check the deck against
+        #[arg(short, long, value_enum)]
+        format: Format,
+
+        /// Deck file format (guessed from the file extension if not set)
+        #[arg(long, value_enum)]
+        deck_format: Option<DeckFormat>,
+    },
+
+    /// Shows which formats each card in a deck, and the deck as a whole, is legal in
+    Formats {
+        /// Deck file or saved deck to check
+        deck: String,
+
+        /// Show every format instead of only the most played ones
+        #[arg(short, long)]
+        all: bool,
+
+        /// Deck file format (guessed from the file extension if not set)
+        #[arg(long, value_enum)]
+        deck_format: Option<DeckFormat>,
+    },
+
+    /// Shows the mana curve, colour pips, card types and keywords of a deck
+    Stats {
+        /// Deck file or saved deck to analyse
+        deck: String,
+
+        /// Print the stats as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Deck file format (guessed from the file extension if not set)
+        #[arg(long, value_enum)]
+        deck_format: Option<DeckFormat>,
+    },
+
+    /// Checks a deck has enough coloured mana sources to cast its spells
+    Mana {
+        /// Deck file or saved deck to analyse
+        deck: String,
+
+        /// Print the analysis as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Deck file format (guessed from the file extension if not set)
+        #[arg(long, value_enum)]
+        deck_format: Option<DeckFormat>,
+    },
+
+    /// Shows what changed between two versions of a deck and what the additions cost
+    Diff {
+        /// Previous version of the deck, as a file or saved deck (e.g. `burn@2`)
+        old: String,
+
+        /// New version of the deck, as a file or saved deck
+        new: String,
+
         /// Currency format to use
         #[arg(long, value_enum, default_value_t = Currency::Euro)]
         currency: Currency,
 
-        /// Use exact card name for search
-        #[arg(short, long)]
-        exact_match: bool,
+        /// Include basic lands in the upgrade cost
+        #[arg(long)]
+        include_basics: bool,
+
+        /// Deck file format of both decks (guessed from the file extensions if not set)
+        #[arg(long, value_enum)]
+        deck_format: Option<DeckFormat>,
+    },
+
+    /// Simulates opening hands and draws for a deck
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Simulate {
+        #[command(subcommand)]
+        command: Option<SimulateCommands>,
+
+        /// Deck file or saved deck to simulate
+        #[arg(required = true)]
+        deck: Option<String>,
+
+        #[command(flatten)]
+        options: SimulateOptions,
+    },
+
+    /// Manages the cards in your collection
+    Collection {
+        #[command(subcommand)]
+        command: CollectionCommands,
+    },
+
+    /// Plans building several decks at the same time from the collection
+    Plan {
+        /// Deck files or saved decks to build
+        #[arg(required = true)]
+        decks: Vec<String>,
+
+        /// Currency format to use
+        #[arg(long, value_enum, default_value_t = Currency::Euro)]
+        currency: Currency,
+
+        /// Include basic lands in the plan, rather than assuming there are enough
+        #[arg(long)]
+        include_basics: bool,
+
+        /// Deck file format of every deck (guessed from the file extensions if not set)
+        #[arg(long, value_enum)]
+        deck_format: Option<DeckFormat>,
+    },
+
+    /// Saves decks in the database, keeping every revision. Saved decks can be used
+    /// in place of deck files by name, or as `name@revision` for an earlier revision
+    Deck {
+        #[command(subcommand)]
+        command: DeckCommands,
+    },
+
+    /// Records what was paid for cards and tracks profit and loss
+    Ledger {
+        #[command(subcommand)]
+        command: LedgerCommands,
     },
 
     /// Removes the .magedeck directory
     Clean,
 }
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct PriceOptions {
+    /// Currency format to use
+    #[arg(long, value_enum, default_value_t = Currency::Euro)]
+    pub(crate) currency: Currency,
+
+    /// Use exact card name for search
+    #[arg(short, long)]
+    pub(crate) exact_match: bool,
+
+    /// Include basic lands when pricing a deck (e.g. for full-art basics)
+    #[arg(long, requires = "deck")]
+    pub(crate) include_basics: bool,
+
+    /// Automatically use the closest card name for unique, high confidence typos
+    #[arg(long, requires = "deck")]
+    pub(crate) fix: bool,
+
+    /// Only price the copies of each card that aren't already in the collection
+    #[arg(long, requires = "deck")]
+    pub(crate) owned: bool,
+
+    /// Only count owned copies with this finish (can be repeated, any finish if not set)
+    #[arg(long = "finish", value_enum, requires = "owned")]
+    pub(crate) finishes: Vec<Finish>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct SimulateOptions {
+    /// Number of shuffles to simulate
+    #[arg(short, long, default_value_t = 10000, value_parser = clap::value_parser!(u32).range(1..))]
+    pub(crate) trials: u32,
+
+    /// Seed for the shuffles, to reproduce a previous run
+    #[arg(long)]
+    pub(crate) seed: Option<u64>,
+
+    /// Card to track the chance of drawing by `--turn` (can be repeated)
+    #[arg(short, long = "card")]
+    pub(crate) cards: Vec<String>,
+
+    /// Turn to track cards by
+    #[arg(long, default_value_t = 3)]
+    pub(crate) turn: u32,
+
+    /// Simulate being on the play rather than the draw
+    #[arg(long)]
+    pub(crate) play: bool,
+
+    /// Number of lands to report the chance of having in the opening seven
+    #[arg(short, long, default_value_t = 2)]
+    pub(crate) lands: usize,
+
+    /// Fewest lands in a hand that will be kept instead of mulliganed
+    #[arg(long, default_value_t = 2)]
+    pub(crate) min_lands: usize,
+
+    /// Most lands in a hand that will be kept instead of mulliganed
+    #[arg(long, default_value_t = 5)]
+    pub(crate) max_lands: usize,
+
+    /// Deck file format (guessed from the file extension if not set)
+    #[arg(long, value_enum)]
+    pub(crate) deck_format: Option<DeckFormat>,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum SimulateCommands {
+    /// Exact odds of drawing a number of copies (e.g. 4 copies in 60 cards within 7 draws)
+    Hypergeometric {
+        /// Number of cards in the deck
+        #[arg(short, long, default_value_t = 60)]
+        deck_size: u32,
+
+        /// Copies of the card(s) in the deck
+        #[arg(short, long)]
+        copies: u32,
+
+        /// Number of cards drawn
+        #[arg(short = 'n', long, default_value_t = 7)]
+        draws: u32,
+
+        /// Number of copies wanted
+        #[arg(short = 'k', long, default_value_t = 1)]
+        hits: u32,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum CollectionCommands {
+    /// Adds cards to the collection
+    Add {
+        #[command(flatten)]
+        entry: CollectionEntry,
+    },
+
+    /// Removes cards from the collection
+    Remove {
+        #[command(flatten)]
+        entry: CollectionEntry,
+    },
+
+    /// Lists the cards in the collection
+    List {
+        /// Only list cards with names containing this
+        filter: Option<String>,
+    },
+
+    /// Values the collection using the synced prices
+    Value {
+        /// Currency format to use
+        #[arg(long, value_enum, default_value_t = Currency::Euro)]
+        currency: Currency,
+    },
+
+    /// Imports a CSV collection export from a card scanning app
+    Import {
+        /// CSV file exported by the app
+        file: String,
+
+        /// App the file was exported from
+        #[arg(short, long, value_enum)]
+        from: ScannerApp,
+
+        /// Show what would be imported without changing the collection
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct CollectionEntry {
+    /// Card name
+    pub(crate) card: String,
+
+    /// Number of copies
+    #[arg(short, long, default_value_t = 1)]
+    pub(crate) quantity: u32,
+
+    /// Set code of the printing (the synced printing is used when adding without one)
+    #[arg(short, long)]
+    pub(crate) set: Option<String>,
+
+    /// Collector number of the printing
+    #[arg(short = 'n', long)]
+    pub(crate) collector_number: Option<String>,
+
+    #[arg(long, value_enum, default_value_t = Finish::Nonfoil)]
+    pub(crate) finish: Finish,
+
+    #[arg(long, value_enum, default_value_t = Condition::NearMint)]
+    pub(crate) condition: Condition,
+
+    /// Language code of the printing
+    #[arg(long, default_value = "en")]
+    pub(crate) language: String,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum LedgerCommands {
+    /// Records a purchase of a card
+    Add {
+        #[command(flatten)]
+        record: LedgerRecord,
+
+        /// Total paid for all copies
+        #[arg(short, long, value_parser = parse_non_negative)]
+        paid: f32,
+    },
+
+    /// Records a sale of a card
+    Sell {
+        #[command(flatten)]
+        record: LedgerRecord,
+
+        /// Total received for all copies
+        #[arg(short, long, value_parser = parse_non_negative)]
+        received: f32,
+    },
+
+    /// Lists every purchase and sale
+    List,
+
+    /// Removes a purchase or sale by its id
+    Remove { id: i64 },
+
+    /// Compares what was paid against the current market value, with gains from sales
+    Report {
+        /// Currency to report in, entries in other currencies aren't included
+        #[arg(long, value_enum, default_value_t = Currency::Euro)]
+        currency: Currency,
+    },
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct LedgerRecord {
+    /// Card name
+    pub(crate) card: String,
+
+    /// Number of copies
+    #[arg(short, long = "qty", default_value_t = 1)]
+    pub(crate) quantity: u32,
+
+    /// Currency the amount is in (foil and etched currencies compare against those prices)
+    #[arg(long, value_enum, default_value_t = Currency::Euro)]
+    pub(crate) currency: Currency,
+
+    /// Date of the purchase or sale as YYYY-MM-DD (today if not set)
+    #[arg(short, long, value_parser = parse_date)]
+    pub(crate) date: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum DeckCommands {
+    /// Saves a deck file as the next revision of a saved deck
+    Save {
+        /// Deck file to save
+        file: String,
+
+        /// Name to save the deck under (the file name if not set)
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Deck file format (guessed from the file extension if not set)
+        #[arg(long, value_enum)]
+        deck_format: Option<DeckFormat>,
+    },
+
+    /// Lists the saved decks
+    List,
+
+    /// Shows a saved deck and its revisions
+    Show {
+        /// Saved deck name, or `name@revision` for an earlier revision
+        name: String,
+    },
+
+    /// Shows how the value of a saved deck has changed across syncs
+    Value {
+        /// Saved deck name
+        name: String,
+
+        /// Currency format to use
+        #[arg(long, value_enum, default_value_t = Currency::Euro)]
+        currency: Currency,
+    },
+
+    /// Removes a saved deck, or a single revision with `name@revision`
+    #[command(visible_alias = "rm")]
+    Remove { name: String },
+}
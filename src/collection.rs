@@ -0,0 +1,153 @@
+use clap::ValueEnum;
+use sqlx::FromRow;
+
+use crate::card::Currency;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(rename_all = "snake_case")]
+pub(crate) enum Finish {
+    Nonfoil,
+    Foil,
+    Etched,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(rename_all = "snake_case")]
+pub(crate) enum Condition {
+    #[clap(name = "m")]
+    Mint,
+    #[clap(name = "nm")]
+    NearMint,
+    #[clap(name = "lp")]
+    LightlyPlayed,
+    #[clap(name = "mp")]
+    ModeratelyPlayed,
+    #[clap(name = "hp")]
+    HeavilyPlayed,
+    #[clap(name = "dmg")]
+    Damaged,
+}
+
+impl std::fmt::Display for Finish {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let finish = match self {
+            Self::Nonfoil => "nonfoil",
+            Self::Foil => "foil",
+            Self::Etched => "etched",
+        };
+
+        write!(f, "{finish}")
+    }
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let condition = match self {
+            Self::Mint => "M",
+            Self::NearMint => "NM",
+            Self::LightlyPlayed => "LP",
+            Self::ModeratelyPlayed => "MP",
+            Self::HeavilyPlayed => "HP",
+            Self::Damaged => "DMG",
+        };
+
+        write!(f, "{condition}")
+    }
+}
+
+impl Finish {
+    /// Price to use for this finish in a currency, e.g. foils priced in euro use
+    /// the foil euro price. Currencies that already name a finish are left as is
+    pub(crate) fn currency(self, currency: Currency) -> Currency {
+        match (self, currency) {
+            (Self::Foil | Self::Etched, Currency::Euro) => Currency::EuroFoil,
+            (Self::Foil, Currency::Usd) => Currency::UsdFoil,
+            (Self::Etched, Currency::Usd) => Currency::UsdEtched,
+            _ => currency,
+        }
+    }
+}
+
+/// Owned copies of a single printing, finish, condition and language of a card
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub(crate) struct CollectionCard {
+    pub(crate) name: String,
+    #[sqlx(rename = "set_tag")]
+    pub(crate) set: String,
+    pub(crate) collector_number: String,
+    pub(crate) finish: Finish,
+    pub(crate) condition: Condition,
+    pub(crate) language: String,
+    pub(crate) quantity: u32,
+}
+
+impl CollectionCard {
+    pub(crate) fn new(name: impl Into<String>, quantity: u32) -> Self {
+        Self {
+            name: name.into(),
+            set: String::new(),
+            collector_number: String::new(),
+            finish: Finish::Nonfoil,
+            condition: Condition::NearMint,
+            language: "en".to_string(),
+            quantity,
+        }
+    }
+}
+
+impl std::fmt::Display for CollectionCard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x {}", self.quantity, self.name)?;
+        match (self.set.is_empty(), self.collector_number.is_empty()) {
+            (true, _) => {}
+            (false, true) => write!(f, " ({})", self.set)?,
+            (false, false) => write!(f, " ({} {})", self.set, self.collector_number)?,
+        }
+
+        write!(
+            f,
+            " - {}, {}, {}",
+            self.finish, self.condition, self.language
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prices_finishes_in_matching_currency() {
+        assert_eq!(Finish::Nonfoil.currency(Currency::Euro), Currency::Euro);
+        assert_eq!(Finish::Foil.currency(Currency::Euro), Currency::EuroFoil);
+        assert_eq!(Finish::Etched.currency(Currency::Euro), Currency::EuroFoil);
+        assert_eq!(Finish::Foil.currency(Currency::Usd), Currency::UsdFoil);
+        assert_eq!(Finish::Etched.currency(Currency::Usd), Currency::UsdEtched);
+        assert_eq!(Finish::Foil.currency(Currency::Tix), Currency::Tix);
+        assert_eq!(
+            Finish::Nonfoil.currency(Currency::UsdFoil),
+            Currency::UsdFoil
+        );
+    }
+
+    #[test]
+    fn displays_printing_when_known() {
+        let mut card = CollectionCard::new("Lightning Bolt", 4);
+        assert_eq!(card.to_string(), "4x Lightning Bolt - nonfoil, NM, en");
+
+        card.set = "M10".to_string();
+        assert_eq!(
+            card.to_string(),
+            "4x Lightning Bolt (M10) - nonfoil, NM, en"
+        );
+
+        card.collector_number = "146".to_string();
+        card.finish = Finish::Foil;
+        card.condition = Condition::LightlyPlayed;
+        card.language = "ja".to_string();
+        assert_eq!(
+            card.to_string(),
+            "4x Lightning Bolt (M10 146) - foil, LP, ja"
+        );
+    }
+}
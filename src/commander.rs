@@ -0,0 +1,599 @@
+use crate::{
+    card::{DbCard, Deck, DeckEntry, DeckSection, COLOURS},
+    legality::{Format, Violation},
+};
+
+const COMPANION_MARKER: &str = "companion —";
+
+/// Checks the command zone of a deck: commander eligibility, partner style
+/// pairings, colour identity and companions. `cards` holds the database entry
+/// for each deck entry in the same order
+pub(crate) fn check_commander(
+    deck: &Deck,
+    cards: &[Option<DbCard>],
+    format: Format,
+) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let entries: Vec<(&DeckEntry, &DbCard)> = deck
+        .cards
+        .iter()
+        .zip(cards)
+        .filter_map(|(entry, card)| Some((entry, card.as_ref()?)))
+        .collect();
+
+    let commanders: Vec<(&DeckEntry, &DbCard)> = entries
+        .iter()
+        .filter(|(entry, _)| entry.section == DeckSection::Commander)
+        .copied()
+        .collect();
+
+    if commanders.is_empty() {
+        violations.push(Violation::new(
+            "No commander found, mark it with `# !Commander` or list it under a `Commander` header",
+            &[],
+        ));
+        return violations;
+    }
+
+    let commander_entries: Vec<&DeckEntry> = commanders.iter().map(|(entry, _)| *entry).collect();
+    let commander_count: u32 = commander_entries.iter().map(|entry| entry.quantity).sum();
+    match commanders.as_slice() {
+        [(entry, card)] if commander_count == 1 => {
+            if !is_eligible(card, format) {
+                violations.push(Violation::new(
+                    format!("'{}' can't be your commander in {format}", name(card)),
+                    &[entry],
+                ));
+            }
+        }
+        [(first_entry, first), (second_entry, second)] if commander_count == 2 => {
+            violations.extend(check_pairing(
+                (first_entry, first),
+                (second_entry, second),
+                format,
+            ));
+        }
+        _ => violations.push(Violation::new(
+            format!("{commander_count} commanders listed, at most two partners are allowed"),
+            &commander_entries,
+        )),
+    }
+
+    let identity: Vec<char> = commanders
+        .iter()
+        .flat_map(|(_, card)| card.color_identity.as_deref().unwrap_or_default().chars())
+        .collect();
+
+    let outside: Vec<&DeckEntry> = entries
+        .iter()
+        .filter(|(_, card)| {
+            card.color_identity
+                .as_deref()
+                .unwrap_or_default()
+                .chars()
+                .any(|colour| !identity.contains(&colour))
+        })
+        .map(|(entry, _)| *entry)
+        .collect();
+
+    if !outside.is_empty() {
+        violations.push(Violation::new(
+            format!(
+                "Cards outside the commander's colour identity ({})",
+                identity_name(&identity)
+            ),
+            &outside,
+        ));
+    }
+
+    let companions: Vec<(&DeckEntry, &DbCard)> = entries
+        .iter()
+        .filter(|(entry, card)| entry.section == DeckSection::Sideboard && is_companion(card))
+        .copied()
+        .collect();
+
+    match companions.as_slice() {
+        [] => {}
+        [(entry, companion)] => {
+            let deck_cards: Vec<&DbCard> = entries
+                .iter()
+                .filter(|(entry, _)| entry.section != DeckSection::Sideboard)
+                .map(|(_, card)| *card)
+                .collect();
+
+            if let Some(reason) = companion_condition(companion, &deck_cards) {
+                violations.push(Violation::new(
+                    format!(
+                        "Companion '{}' condition not met: {reason}",
+                        name(companion)
+                    ),
+                    &[entry],
+                ));
+            }
+        }
+        _ => {
+            let companion_entries: Vec<&DeckEntry> =
+                companions.iter().map(|(entry, _)| *entry).collect();
+            violations.push(Violation::new(
+                "Only one companion is allowed",
+                &companion_entries,
+            ));
+        }
+    }
+
+    violations
+}
+
+/// Whether a card has the companion ability, letting it sit outside a
+/// Commander deck's 100 cards
+pub(crate) fn is_companion(card: &DbCard) -> bool {
+    oracle_lines(card).any(|line| line.starts_with(COMPANION_MARKER))
+}
+
+fn check_pairing(
+    (first_entry, first): (&DeckEntry, &DbCard),
+    (second_entry, second): (&DeckEntry, &DbCard),
+    format: Format,
+) -> Vec<Violation> {
+    let entries = [first_entry, second_entry];
+
+    // The Oathbreaker command zone holds a planeswalker and its signature spell
+    if format == Format::Oathbreaker {
+        let (walkers, spells): (Vec<&DbCard>, Vec<&DbCard>) = [first, second]
+            .into_iter()
+            .partition(|card| card.has_type("Planeswalker"));
+        let valid = walkers.len() == 1
+            && spells
+                .iter()
+                .all(|card| card.has_type("Instant") || card.has_type("Sorcery"));
+
+        if valid {
+            return Vec::new();
+        }
+
+        return vec![Violation::new(
+            "Oathbreaker needs one planeswalker and one instant or sorcery signature spell",
+            &entries,
+        )];
+    }
+
+    let pairs = [
+        ((first_entry, first), second),
+        ((second_entry, second), first),
+    ];
+    for (commander, partner) in pairs {
+        // Backgrounds aren't creatures so only the commander choosing one needs to be eligible
+        if has_ability(commander.1, "choose a background") && partner.has_type("Background") {
+            return eligibility_violations(&[commander], format);
+        }
+
+        if has_ability(commander.1, "doctor's companion") && partner.has_type("Time Lord Doctor") {
+            return eligibility_violations(&[(first_entry, first), (second_entry, second)], format);
+        }
+    }
+
+    let partnered = (has_ability(first, "partner") && has_ability(second, "partner"))
+        || (has_ability(first, "friends forever") && has_ability(second, "friends forever"))
+        || (partners_with(first, second) && partners_with(second, first))
+        || partner_group(first).is_some_and(|group| partner_group(second) == Some(group));
+
+    if !partnered {
+        return vec![Violation::new(
+            format!(
+                "'{}' and '{}' can't be paired as commanders",
+                name(first),
+                name(second)
+            ),
+            &entries,
+        )];
+    }
+
+    eligibility_violations(&[(first_entry, first), (second_entry, second)], format)
+}
+
+fn eligibility_violations(commanders: &[(&DeckEntry, &DbCard)], format: Format) -> Vec<Violation> {
+    commanders
+        .iter()
+        .filter(|(_, card)| !is_eligible(card, format))
+        .map(|(entry, card)| {
+            Violation::new(
+                format!("'{}' can't be your commander in {format}", name(card)),
+                &[entry],
+            )
+        })
+        .collect()
+}
+
+/// Whether a card can be a commander on its own in a format
+pub(crate) fn is_eligible(card: &DbCard, format: Format) -> bool {
+    if oracle_lines(card).any(|line| line.contains("can be your commander")) {
+        return true;
+    }
+
+    let legendary = card.has_type("Legendary");
+    match format {
+        Format::Oathbreaker => card.has_type("Planeswalker"),
+        Format::PauperCommander => card.has_type("Creature"),
+        Format::Brawl | Format::StandardBrawl => {
+            legendary && (card.has_type("Creature") || card.has_type("Planeswalker"))
+        }
+        _ => legendary && card.has_type("Creature"),
+    }
+}
+
+/// Checks a companion's deck building condition against the starting deck,
+/// returning why it isn't met. Companions with conditions that can't be
+/// checked from the stored card data are assumed to be fine
+fn companion_condition(companion: &DbCard, deck: &[&DbCard]) -> Option<String> {
+    let nonland = || deck.iter().filter(|card| !card.has_type("Land"));
+    let cmc = |card: &&&DbCard| card.cmc.unwrap_or_default() as u32;
+    let first_failing = |cards: Vec<&&DbCard>| cards.first().map(|card| name(card).to_string());
+
+    match name(companion) {
+        "Gyruda, Doom of Depths" => {
+            first_failing(nonland().filter(|card| cmc(card) % 2 != 0).collect())
+                .map(|card| format!("'{card}' has an odd mana value"))
+        }
+        "Obosh, the Preypiercer" => {
+            first_failing(nonland().filter(|card| cmc(card) % 2 == 0).collect())
+                .map(|card| format!("'{card}' has an even mana value"))
+        }
+        "Keruga, the Macrosage" => first_failing(nonland().filter(|card| cmc(card) < 3).collect())
+            .map(|card| format!("'{card}' has mana value less than 3")),
+        "Lurrus of the Dream-Den" => first_failing(
+            deck.iter()
+                .filter(|card| is_permanent(card) && !card.has_type("Land") && cmc(card) > 2)
+                .collect(),
+        )
+        .map(|card| format!("'{card}' is a permanent with mana value greater than 2")),
+        "Kaheera, the Orphanguard" => first_failing(
+            deck.iter()
+                .filter(|card| card.has_type("Creature"))
+                .filter(|card| {
+                    !["Cat", "Elemental", "Nightmare", "Dinosaur", "Beast"]
+                        .iter()
+                        .any(|subtype| card.has_type(subtype))
+                })
+                .collect(),
+        )
+        .map(|card| format!("'{card}' isn't a Cat, Elemental, Nightmare, Dinosaur or Beast")),
+        "Jegantha, the Wellspring" => first_failing(
+            deck.iter()
+                .filter(|card| has_repeated_symbol(card.mana_cost.as_deref().unwrap_or_default()))
+                .collect(),
+        )
+        .map(|card| format!("'{card}' has more than one of the same mana symbol")),
+        "Umori, the Collector" => {
+            let shares_type = [
+                "Artifact",
+                "Creature",
+                "Enchantment",
+                "Instant",
+                "Planeswalker",
+                "Sorcery",
+                "Battle",
+            ]
+            .iter()
+            .any(|card_type| nonland().all(|card| card.has_type(card_type)));
+            (!shares_type).then(|| "nonland cards don't all share a card type".to_string())
+        }
+        "Yorion, Sky Nomad" => Some("a singleton deck can't hold 20 extra cards".to_string()),
+        _ => None,
+    }
+}
+
+fn has_repeated_symbol(mana_cost: &str) -> bool {
+    // Each face of a split card has its own cost
+    mana_cost.split(" // ").any(|cost| {
+        let symbols: Vec<&str> = cost
+            .split(['{', '}'])
+            .filter(|symbol| !symbol.is_empty() && symbol.parse::<u32>().is_err())
+            .collect();
+
+        symbols
+            .iter()
+            .enumerate()
+            .any(|(i, symbol)| symbols[i + 1..].contains(symbol))
+    })
+}
+
+fn is_permanent(card: &DbCard) -> bool {
+    !card.has_type("Instant") && !card.has_type("Sorcery")
+}
+
+/// Checks for a keyword ability on its own line, ignoring reminder text
+/// (e.g. `Partner` but not `Partner with Pir, Imaginative Rascal`)
+fn has_ability(card: &DbCard, ability: &str) -> bool {
+    oracle_lines(card).any(|line| {
+        let line = line.split(" (").next().unwrap_or_default().trim();
+        line == ability
+    })
+}
+
+fn partners_with(card: &DbCard, partner: &DbCard) -> bool {
+    let partner_name = name(partner).to_lowercase();
+    oracle_lines(card).any(|line| {
+        line.strip_prefix("partner with ")
+            .is_some_and(|named| named.starts_with(&partner_name))
+    })
+}
+
+/// Group named by a `Partner—<Group>` ability (e.g. `Partner—Survivors`), which
+/// only pairs with commanders partnering within the same group
+fn partner_group(card: &DbCard) -> Option<String> {
+    oracle_lines(card).find_map(|line| {
+        let line = line.split(" (").next().unwrap_or_default().trim();
+        line.strip_prefix("partner—")
+            .map(|group| group.trim().to_string())
+    })
+}
+
+fn oracle_lines(card: &DbCard) -> impl Iterator<Item = String> + '_ {
+    card.oracle_text
+        .as_deref()
+        .unwrap_or_default()
+        .lines()
+        .map(|line| line.trim().to_lowercase())
+}
+
+fn name(card: &DbCard) -> &str {
+    card.name.as_deref().unwrap_or_default()
+}
+
+fn identity_name(identity: &[char]) -> String {
+    let identity: String = COLOURS
+        .iter()
+        .filter(|colour| identity.contains(colour))
+        .collect();
+
+    if identity.is_empty() {
+        return "colourless".to_string();
+    }
+
+    identity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(name: &str, type_line: &str, oracle_text: &str, identity: &str) -> DbCard {
+        DbCard {
+            name: Some(name.to_string()),
+            type_line: Some(type_line.to_string()),
+            oracle_text: Some(oracle_text.to_string()),
+            color_identity: Some(identity.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn creature(name: &str, oracle_text: &str, identity: &str) -> DbCard {
+        card(name, "Legendary Creature — Human", oracle_text, identity)
+    }
+
+    fn permanent(name: &str, cmc: f32) -> DbCard {
+        DbCard {
+            cmc: Some(cmc),
+            ..card(name, "Artifact", "", "")
+        }
+    }
+
+    fn check(cards: Vec<(DeckSection, DbCard)>, format: Format) -> Vec<String> {
+        let entries = cards
+            .iter()
+            .map(|(section, card)| DeckEntry::new(1, card.name.clone().unwrap(), *section))
+            .collect();
+        let deck = Deck::new(entries);
+        let cards: Vec<Option<DbCard>> = cards.into_iter().map(|(_, card)| Some(card)).collect();
+
+        check_commander(&deck, &cards, format)
+            .into_iter()
+            .map(|violation| violation.to_string())
+            .collect()
+    }
+
+    fn pair(first: DbCard, second: DbCard) -> Vec<String> {
+        check(
+            vec![
+                (DeckSection::Commander, first),
+                (DeckSection::Commander, second),
+            ],
+            Format::Commander,
+        )
+    }
+
+    #[test]
+    fn pairs_partners() {
+        let thrasios = creature("Thrasios, Triton Hero", "{4}: Scry 1, then reveal the top card of your library.\nPartner (You can have two commanders if both have partner.)", "GU");
+        let tymna = creature(
+            "Tymna the Weaver",
+            "Lifelink\nPartner (You can have two commanders if both have partner.)",
+            "WB",
+        );
+        let krenko = creature(
+            "Krenko, Mob Boss",
+            "{T}: Create X 1/1 red Goblin creature tokens.",
+            "R",
+        );
+
+        assert!(pair(thrasios.clone(), tymna).is_empty());
+        assert_eq!(
+            pair(thrasios, krenko),
+            ["'Thrasios, Triton Hero' and 'Krenko, Mob Boss' can't be paired as commanders: 1 Thrasios, Triton Hero # !Commander; 1 Krenko, Mob Boss # !Commander"]
+        );
+    }
+
+    #[test]
+    fn pairs_partner_with_only_with_named_card() {
+        let pir = creature("Pir, Imaginative Rascal", "Partner with Toothy, Imaginary Friend (When this creature enters, target player may put Toothy into their hand from their library, then shuffle.)", "G");
+        let toothy = creature("Toothy, Imaginary Friend", "Partner with Pir, Imaginative Rascal (When this creature enters, target player may put Pir into their hand from their library, then shuffle.)", "U");
+        let tymna = creature(
+            "Tymna the Weaver",
+            "Partner (You can have two commanders if both have partner.)",
+            "WB",
+        );
+
+        assert!(pair(pir.clone(), toothy).is_empty());
+        assert_eq!(pair(pir, tymna).len(), 1);
+    }
+
+    #[test]
+    fn pairs_partner_groups() {
+        let survivors = |name: &str| {
+            creature(
+                name,
+                "Partner—Survivors (You can have two commanders if both have this ability.)",
+                "G",
+            )
+        };
+        let father = creature(
+            "Kratos, Stoic Father",
+            "Partner—Father & son (You can have two commanders if both have this ability.)",
+            "R",
+        );
+
+        assert!(pair(
+            survivors("Tyvar, the Pummeler"),
+            survivors("Marina Vendrell")
+        )
+        .is_empty());
+        assert_eq!(pair(survivors("Tyvar, the Pummeler"), father).len(), 1);
+    }
+
+    #[test]
+    fn pairs_backgrounds_and_doctors() {
+        let wilson = creature(
+            "Wilson, Refined Grizzly",
+            "Choose a Background (You can have a Background as a second commander.)",
+            "G",
+        );
+        let background = card(
+            "Raised by Giants",
+            "Legendary Enchantment — Background",
+            "Commander creatures you own have base power and toughness 10/10.",
+            "G",
+        );
+        let companion = creature(
+            "Rose Tyler",
+            "Doctor's companion (You can have two commanders if the other is the Doctor.)",
+            "W",
+        );
+        let doctor = card(
+            "The Tenth Doctor",
+            "Legendary Creature — Time Lord Doctor",
+            "Allons-y!",
+            "URW",
+        );
+
+        assert!(pair(wilson, background.clone()).is_empty());
+        assert!(pair(doctor, companion).is_empty());
+        assert_eq!(
+            check(vec![(DeckSection::Commander, background)], Format::Commander),
+            ["'Raised by Giants' can't be your commander in Commander: 1 Raised by Giants # !Commander"]
+        );
+    }
+
+    #[test]
+    fn checks_oathbreaker_and_signature_spell() {
+        let walker = card(
+            "Chandra, Torch of Defiance",
+            "Legendary Planeswalker — Chandra",
+            "+1: Add {R}{R}.",
+            "R",
+        );
+        let spell = card(
+            "Lightning Bolt",
+            "Instant",
+            "Lightning Bolt deals 3 damage to any target.",
+            "R",
+        );
+        let other_walker = card(
+            "Chandra, Acolyte of Flame",
+            "Legendary Planeswalker — Chandra",
+            "0: Put a loyalty counter on each red planeswalker you control.",
+            "R",
+        );
+
+        let zone = |first: &DbCard, second: &DbCard| {
+            check(
+                vec![
+                    (DeckSection::Commander, first.clone()),
+                    (DeckSection::Commander, second.clone()),
+                ],
+                Format::Oathbreaker,
+            )
+        };
+
+        assert!(zone(&walker, &spell).is_empty());
+        assert!(zone(&spell, &walker).is_empty());
+        assert_eq!(zone(&walker, &other_walker).len(), 1);
+    }
+
+    #[test]
+    fn reports_cards_outside_colour_identity() {
+        let krenko = creature(
+            "Krenko, Mob Boss",
+            "{T}: Create X 1/1 red Goblin creature tokens.",
+            "R",
+        );
+        let deck = vec![
+            (DeckSection::Commander, krenko),
+            (
+                DeckSection::Main,
+                card("Lightning Bolt", "Instant", "", "R"),
+            ),
+            (DeckSection::Main, card("Sol Ring", "Artifact", "", "")),
+            (DeckSection::Main, card("Counterspell", "Instant", "", "U")),
+        ];
+
+        assert_eq!(
+            check(deck, Format::Commander),
+            ["Cards outside the commander's colour identity (R): 1 Counterspell"]
+        );
+    }
+
+    #[test]
+    fn checks_companion_conditions() {
+        let krenko = creature(
+            "Krenko, Mob Boss",
+            "{T}: Create X 1/1 red Goblin creature tokens.",
+            "R",
+        );
+        let lurrus = card("Lurrus of the Dream-Den", "Legendary Creature — Cat Nightmare", "Companion — Each permanent card in your starting deck has mana value 2 or less.\nLifelink", "WB");
+        let gyruda = card(
+            "Gyruda, Doom of Depths",
+            "Legendary Creature — Demon Kraken",
+            "Companion — Your starting deck contains only cards with even mana values.",
+            "UB",
+        );
+
+        let deck = |companions: Vec<DbCard>, cards: Vec<DbCard>| {
+            let mut deck = vec![(DeckSection::Commander, krenko.clone())];
+            deck.extend(cards.into_iter().map(|card| (DeckSection::Main, card)));
+            deck.extend(
+                companions
+                    .into_iter()
+                    .map(|card| (DeckSection::Sideboard, card)),
+            );
+            // Ignore the colour identity, only the companion rules are under test
+            check(deck, Format::Commander)
+                .into_iter()
+                .filter(|violation| !violation.starts_with("Cards outside"))
+                .collect::<Vec<String>>()
+        };
+
+        assert!(deck(vec![lurrus.clone()], vec![permanent("Sol Ring", 1.0)]).is_empty());
+        assert_eq!(
+            deck(vec![lurrus.clone()], vec![permanent("Mind Stone", 2.0), permanent("Coalition Relic", 3.0)]),
+            ["Companion 'Lurrus of the Dream-Den' condition not met: 'Coalition Relic' is a permanent with mana value greater than 2: SB: 1 Lurrus of the Dream-Den"]
+        );
+        assert_eq!(
+            deck(vec![gyruda.clone()], vec![permanent("Sol Ring", 1.0)]),
+            ["Companion 'Gyruda, Doom of Depths' condition not met: 'Sol Ring' has an odd mana value: SB: 1 Gyruda, Doom of Depths"]
+        );
+        assert_eq!(
+            deck(vec![lurrus, gyruda], Vec::new()),
+            ["Only one companion is allowed: SB: 1 Lurrus of the Dream-Den; SB: 1 Gyruda, Doom of Depths"]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{
+    card::{Deck, DeckSection},
+    utils::normalise_name,
+};
+
+/// Sections in the order changes are listed
+pub(crate) const SECTIONS: [DeckSection; 3] = [
+    DeckSection::Commander,
+    DeckSection::Main,
+    DeckSection::Sideboard,
+];
+
+/// A change in the number of copies of a card in one section of a deck
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DiffEntry {
+    pub(crate) section: DeckSection,
+    pub(crate) name: String,
+    pub(crate) old: u32,
+    pub(crate) new: u32,
+}
+
+impl std::fmt::Display for DiffEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let change = match (self.old, self.new) {
+            (0, _) => "new".to_string(),
+            (_, 0) => "removed".to_string(),
+            (old, new) => format!("{old} -> {new}"),
+        };
+
+        if self.new > self.old {
+            write!(f, "+{} {} ({change})", self.new - self.old, self.name)
+        } else {
+            write!(f, "-{} {} ({change})", self.old - self.new, self.name)
+        }
+    }
+}
+
+/// Differences between two versions of a deck
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct DeckDiff {
+    pub(crate) changes: Vec<DiffEntry>,
+}
+
+impl DeckDiff {
+    pub(crate) fn new(old: &Deck, new: &Deck) -> Self {
+        let mut changes = Vec::new();
+        for section in SECTIONS {
+            let old_cards = section_counts(old, section);
+            let new_cards = section_counts(new, section);
+
+            for (key, name, old) in old_cards.iter() {
+                let new = count_of(&new_cards, key);
+                if new != *old {
+                    changes.push(DiffEntry {
+                        section,
+                        name: name.clone(),
+                        old: *old,
+                        new,
+                    });
+                }
+            }
+
+            for (key, name, new) in new_cards.iter() {
+                if !old_cards.iter().any(|(k, _, _)| k == key) {
+                    changes.push(DiffEntry {
+                        section,
+                        name: name.clone(),
+                        old: 0,
+                        new: *new,
+                    });
+                }
+            }
+        }
+
+        Self { changes }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub(crate) fn section(&self, section: DeckSection) -> impl Iterator<Item = &DiffEntry> {
+        self.changes
+            .iter()
+            .filter(move |change| change.section == section)
+    }
+
+    /// Copies of each card that need to be added across the whole deck, so cards
+    /// moved between sections aren't counted as additions
+    pub(crate) fn additions(&self) -> Vec<(String, u32)> {
+        let mut totals: Vec<(String, String, i64)> = Vec::new();
+        for change in self.changes.iter() {
+            let key = normalise_name(&change.name);
+            let delta = change.new as i64 - change.old as i64;
+            match totals.iter_mut().find(|(k, _, _)| *k == key) {
+                Some((_, _, total)) => *total += delta,
+                None => totals.push((key, change.name.clone(), delta)),
+            }
+        }
+
+        totals
+            .into_iter()
+            .filter(|(_, _, total)| *total > 0)
+            .map(|(_, name, total)| (name, total as u32))
+            .collect()
+    }
+}
+
+/// Totals the copies of each card in a section, merging entries for the same
+/// card (e.g. different printings)
+fn section_counts(deck: &Deck, section: DeckSection) -> Vec<(String, String, u32)> {
+    let mut counts: Vec<(String, String, u32)> = Vec::new();
+    for entry in deck.cards.iter().filter(|entry| entry.section == section) {
+        let key = normalise_name(&entry.name);
+        match counts.iter_mut().find(|(k, _, _)| *k == key) {
+            Some((_, _, quantity)) => *quantity += entry.quantity,
+            None => counts.push((key, entry.name.clone(), entry.quantity)),
+        }
+    }
+
+    counts
+}
+
+fn count_of(counts: &[(String, String, u32)], key: &str) -> u32 {
+    counts
+        .iter()
+        .find(|(k, _, _)| k == key)
+        .map(|(_, _, quantity)| *quantity)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::DeckEntry;
+
+    fn deck(cards: &[(u32, &str, DeckSection)]) -> Deck {
+        Deck::new(
+            cards
+                .iter()
+                .map(|(quantity, name, section)| DeckEntry::new(*quantity, *name, *section))
+                .collect(),
+        )
+    }
+
+    fn changes(diff: &DeckDiff) -> Vec<String> {
+        diff.changes
+            .iter()
+            .map(|change| change.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn lists_changes_by_section() {
+        let old = deck(&[
+            (4, "Lightning Bolt", DeckSection::Main),
+            (2, "Lava Spike", DeckSection::Main),
+            (20, "Mountain", DeckSection::Main),
+            (2, "Pyroblast", DeckSection::Sideboard),
+        ]);
+        let new = deck(&[
+            (4, "Lightning Bolt", DeckSection::Main),
+            (4, "Lava Spike", DeckSection::Main),
+            (18, "Mountain", DeckSection::Main),
+            (2, "Skewer the Critics", DeckSection::Main),
+        ]);
+
+        let diff = DeckDiff::new(&old, &new);
+
+        assert_eq!(
+            changes(&diff),
+            [
+                "+2 Lava Spike (2 -> 4)",
+                "-2 Mountain (20 -> 18)",
+                "+2 Skewer the Critics (new)",
+                "-2 Pyroblast (removed)",
+            ]
+        );
+        assert_eq!(diff.section(DeckSection::Sideboard).count(), 1);
+        assert_eq!(
+            diff.additions(),
+            [
+                ("Lava Spike".to_string(), 2),
+                ("Skewer the Critics".to_string(), 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn merges_printings_and_matches_names_loosely() {
+        let old = deck(&[
+            (2, "Lightning Bolt", DeckSection::Main),
+            (2, "Lightning Bolt", DeckSection::Main),
+            (1, "Jötun Grunt", DeckSection::Main),
+        ]);
+        let new = deck(&[
+            (4, "lightning bolt", DeckSection::Main),
+            (1, "Jotun Grunt", DeckSection::Main),
+        ]);
+
+        assert!(DeckDiff::new(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn moving_cards_between_sections_adds_nothing() {
+        let old = deck(&[
+            (3, "Lightning Bolt", DeckSection::Main),
+            (1, "Lightning Bolt", DeckSection::Sideboard),
+        ]);
+        let new = deck(&[(4, "Lightning Bolt", DeckSection::Main)]);
+
+        let diff = DeckDiff::new(&old, &new);
+
+        assert_eq!(
+            changes(&diff),
+            ["+1 Lightning Bolt (3 -> 4)", "-1 Lightning Bolt (removed)"]
+        );
+        assert!(diff.additions().is_empty());
+    }
+}
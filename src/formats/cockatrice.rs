@@ -0,0 +1,181 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::card::{Deck, DeckEntry, DeckSection};
+
+const XML_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+const MAIN_ZONE: &str = "main";
+const SIDEBOARD_ZONE: &str = "side";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename = "cockatrice_deck")]
+struct CockatriceDeck {
+    #[serde(rename = "@version", default)]
+    version: String,
+    #[serde(default)]
+    deckname: String,
+    #[serde(default)]
+    comments: String,
+    #[serde(rename = "zone", default)]
+    zones: Vec<CockatriceZone>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CockatriceZone {
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "card", default)]
+    cards: Vec<CockatriceCard>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CockatriceCard {
+    #[serde(rename = "@number")]
+    number: u32,
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(
+        rename = "@setShortName",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    set: Option<String>,
+    #[serde(
+        rename = "@collectorNumber",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    collector_number: Option<String>,
+    /// Marks commanders, which Cockatrice itself keeps in the main zone
+    #[serde(
+        rename = "@commander",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    commander: Option<bool>,
+}
+
+/// Parses a Cockatrice `.cod` XML deck
+pub(crate) fn parse(content: &str) -> Result<Vec<DeckEntry>> {
+    let deck: CockatriceDeck =
+        quick_xml::de::from_str(content).context("parsing cockatrice deck")?;
+
+    let mut cards = Vec::new();
+    for zone in deck.zones {
+        let section = match zone.name.as_str() {
+            MAIN_ZONE => DeckSection::Main,
+            SIDEBOARD_ZONE => DeckSection::Sideboard,
+            // Token zones aren't part of the playable deck
+            _ => continue,
+        };
+
+        for card in zone.cards {
+            let section = if card.commander == Some(true) {
+                DeckSection::Commander
+            } else {
+                section
+            };
+
+            let mut entry = DeckEntry::new(card.number, card.name, section);
+            entry.set = card.set.map(|set| set.to_uppercase());
+            entry.collector_number = card.collector_number;
+            cards.push(entry);
+        }
+    }
+
+    Ok(cards)
+}
+
+/// Writes a deck out as a Cockatrice `.cod` XML deck
+pub(crate) fn write(deck: &Deck) -> Result<String> {
+    let zone = |name: &str, sections: &[DeckSection]| CockatriceZone {
+        name: name.to_string(),
+        cards: deck
+            .cards
+            .iter()
+            .filter(|card| sections.contains(&card.section))
+            .map(|card| CockatriceCard {
+                number: card.quantity,
+                name: card.name.clone(),
+                set: card.set.clone(),
+                collector_number: card.collector_number.clone(),
+                commander: (card.section == DeckSection::Commander).then_some(true),
+            })
+            .collect(),
+    };
+
+    // Cockatrice has no commander zone so commanders are kept in the main deck,
+    // marked so they're read back as commanders
+    let cockatrice_deck = CockatriceDeck {
+        version: "1".to_string(),
+        deckname: deck.name.clone().unwrap_or_default(),
+        comments: String::new(),
+        zones: vec![
+            zone(MAIN_ZONE, &[DeckSection::Commander, DeckSection::Main]),
+            zone(SIDEBOARD_ZONE, &[DeckSection::Sideboard]),
+        ],
+    };
+
+    let mut output = String::new();
+    let mut serializer = quick_xml::se::Serializer::new(&mut output);
+    serializer.indent(' ', 4);
+    cockatrice_deck
+        .serialize(serializer)
+        .context("serialising cockatrice deck")?;
+
+    Ok(format!("{XML_HEADER}\n{output}\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DECK: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<cockatrice_deck version="1">
+    <deckname>Burn</deckname>
+    <comments></comments>
+    <zone name="main">
+        <card number="4" name="Lightning Bolt" setShortName="m10" collectorNumber="146"/>
+        <card number="10" name="Mountain"/>
+    </zone>
+    <zone name="side">
+        <card number="2" name="Pyroblast"/>
+    </zone>
+    <zone name="tokens">
+        <card number="1" name="Goblin"/>
+    </zone>
+</cockatrice_deck>
+"#;
+
+    #[test]
+    fn parses_zones() {
+        let cards = parse(DECK).unwrap();
+
+        assert_eq!(cards.len(), 3);
+        assert_eq!(cards[0].quantity, 4);
+        assert_eq!(cards[0].name, "Lightning Bolt");
+        assert_eq!(cards[0].section, DeckSection::Main);
+        assert_eq!(cards[0].set.as_deref(), Some("M10"));
+        assert_eq!(cards[0].collector_number.as_deref(), Some("146"));
+        assert_eq!(cards[1].set, None);
+        assert_eq!(cards[2].section, DeckSection::Sideboard);
+    }
+
+    #[test]
+    fn round_trips_deck() {
+        let mut bolt = DeckEntry::new(4, "Lightning Bolt", DeckSection::Main);
+        bolt.set = Some("M10".to_string());
+        bolt.collector_number = Some("146".to_string());
+        let mut deck = Deck::new(vec![
+            DeckEntry::new(1, "Krenko, Mob Boss", DeckSection::Commander),
+            bolt,
+            DeckEntry::new(2, "Pyroblast", DeckSection::Sideboard),
+        ]);
+        deck.name = Some("Goblins".to_string());
+
+        let output = write(&deck).unwrap();
+        assert!(output.contains("<deckname>Goblins</deckname>"));
+        assert!(output.contains(r#"<card number="1" name="Krenko, Mob Boss" commander="true"/>"#));
+        assert_eq!(parse(&output).unwrap(), deck.cards);
+    }
+}
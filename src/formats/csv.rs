@@ -0,0 +1,222 @@
+use anyhow::{Context, Result};
+
+use crate::{
+    card::{Deck, DeckEntry, DeckSection},
+    utils::normalise_header,
+};
+
+// Column names used by the deckbuilding sites (Moxfield, Archidekt, Deckstats)
+// for each field, normalised with `normalise_header`
+const QUANTITY_COLUMNS: [&str; 4] = ["count", "quantity", "qty", "amount"];
+const NAME_COLUMNS: [&str; 3] = ["name", "cardname", "card"];
+const SET_COLUMNS: [&str; 4] = ["edition", "editioncode", "setcode", "set"];
+const COLLECTOR_NUMBER_COLUMNS: [&str; 3] = ["collectornumber", "number", "cn"];
+const FOIL_COLUMNS: [&str; 4] = ["foil", "isfoil", "finish", "printing"];
+const BOARD_COLUMNS: [&str; 4] = ["board", "section", "categories", "category"];
+const MTGO_ID_COLUMNS: [&str; 2] = ["mtgoid", "catid"];
+
+#[derive(Debug, Default)]
+struct Columns {
+    quantity: Option<usize>,
+    name: usize,
+    set: Option<usize>,
+    collector_number: Option<usize>,
+    foil: Option<usize>,
+    board: Option<usize>,
+    mtgo_id: Option<usize>,
+}
+
+impl Columns {
+    fn from_headers(headers: &::csv::StringRecord) -> Result<Self> {
+        let headers: Vec<String> = headers.iter().map(normalise_header).collect();
+        let find = |aliases: &[&str]| {
+            aliases
+                .iter()
+                .find_map(|alias| headers.iter().position(|header| header == alias))
+        };
+
+        let Some(name) = find(&NAME_COLUMNS) else {
+            anyhow::bail!("no card name column found in csv deck");
+        };
+
+        Ok(Self {
+            quantity: find(&QUANTITY_COLUMNS),
+            name,
+            set: find(&SET_COLUMNS),
+            collector_number: find(&COLLECTOR_NUMBER_COLUMNS),
+            foil: find(&FOIL_COLUMNS),
+            board: find(&BOARD_COLUMNS),
+            mtgo_id: find(&MTGO_ID_COLUMNS),
+        })
+    }
+}
+
+/// Parses a CSV deck export from Moxfield, Archidekt or Deckstats
+pub(crate) fn parse(content: &str) -> Result<Vec<DeckEntry>> {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(::csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    let columns = Columns::from_headers(reader.headers().context("reading csv headers")?)?;
+    let mut cards = Vec::new();
+    for record in reader.records() {
+        let record = record.context("reading csv record")?;
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|idx| record.get(idx))
+                .filter(|value| !value.is_empty())
+        };
+
+        let Some(name) = field(Some(columns.name)) else {
+            continue;
+        };
+
+        let Some(section) = parse_section(field(columns.board)) else {
+            continue;
+        };
+
+        let quantity = field(columns.quantity)
+            .and_then(|quantity| quantity.parse::<u32>().ok())
+            .unwrap_or(1);
+
+        let mut entry = DeckEntry::new(quantity, name, section);
+        entry.set = field(columns.set).map(|set| set.to_uppercase());
+        entry.collector_number = field(columns.collector_number).map(|cn| cn.to_string());
+        entry.foil = field(columns.foil).is_some_and(is_foil);
+        entry.mtgo_id = field(columns.mtgo_id).and_then(|id| id.parse::<u32>().ok());
+        cards.push(entry);
+    }
+
+    Ok(cards)
+}
+
+/// Maps a board / category value to a deck section, returning `None`
+/// for boards that aren't part of the deck (e.g. the maybeboard)
+pub(crate) fn parse_section(board: Option<&str>) -> Option<DeckSection> {
+    let Some(board) = board else {
+        return Some(DeckSection::Main);
+    };
+
+    // Archidekt lists several comma separated categories per card
+    let board = board.to_lowercase();
+    let categories: Vec<&str> = board.split(',').map(|category| category.trim()).collect();
+    if categories
+        .iter()
+        .any(|c| c.starts_with("maybe") || *c == "considering")
+    {
+        return None;
+    }
+
+    if categories.iter().any(|c| c.starts_with("commander")) {
+        return Some(DeckSection::Commander);
+    }
+
+    if categories.iter().any(|c| c.starts_with("side")) {
+        return Some(DeckSection::Sideboard);
+    }
+
+    Some(DeckSection::Main)
+}
+
+/// Checks a foil / finish value for a foil printing. Etched foils are a
+/// separate finish with their own prices, so they don't count as foil
+pub(crate) fn is_foil(value: &str) -> bool {
+    matches!(value.to_lowercase().as_str(), "foil" | "true" | "yes" | "1")
+}
+
+/// Writes a deck out as a Moxfield style CSV file
+pub(crate) fn write(deck: &Deck) -> Result<String> {
+    let mut writer = ::csv::Writer::from_writer(Vec::new());
+    writer.write_record([
+        "Count",
+        "Name",
+        "Edition",
+        "Collector Number",
+        "Foil",
+        "Board",
+    ])?;
+    for card in deck.cards.iter() {
+        let board = match card.section {
+            DeckSection::Main => "mainboard",
+            DeckSection::Sideboard => "sideboard",
+            DeckSection::Commander => "commanders",
+        };
+
+        writer.write_record([
+            card.quantity.to_string().as_str(),
+            &card.name,
+            &card.set.as_deref().unwrap_or_default().to_lowercase(),
+            card.collector_number.as_deref().unwrap_or_default(),
+            if card.foil { "foil" } else { "" },
+            board,
+        ])?;
+    }
+
+    let output = writer.into_inner().context("writing csv deck")?;
+    Ok(String::from_utf8(output)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_archidekt_export() {
+        let content = "Quantity,Name,Finish,Edition Code,Category,Collector Number\n\
+            1,\"Krenko, Mob Boss\",Normal,ddt,Commander,15\n\
+            4,Lightning Bolt,Foil,m10,\"Burn,Instant\",146\n\
+            1,Goblin Guide,Etched,2x2,Creature,117\n\
+            2,Pyroblast,Normal,ice,Sideboard,213\n\
+            1,Blood Moon,Normal,9ed,Maybeboard,176\n";
+        let cards = parse(content).unwrap();
+
+        assert_eq!(cards.len(), 4);
+        assert_eq!(cards[0].section, DeckSection::Commander);
+        assert_eq!(cards[1].quantity, 4);
+        assert_eq!(cards[1].name, "Lightning Bolt");
+        assert_eq!(cards[1].set.as_deref(), Some("M10"));
+        assert_eq!(cards[1].collector_number.as_deref(), Some("146"));
+        assert!(cards[1].foil);
+        assert_eq!(cards[1].section, DeckSection::Main);
+        assert!(!cards[2].foil);
+        assert_eq!(cards[3].section, DeckSection::Sideboard);
+    }
+
+    #[test]
+    fn parses_deckstats_export() {
+        let content = "amount,card_name,is_foil,set_code\n3,Counterspell,0,\n,Brainstorm,1,ice\n";
+        let cards = parse(content).unwrap();
+
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].quantity, 3);
+        assert!(!cards[0].foil);
+        assert_eq!(cards[0].set, None);
+        assert_eq!(cards[1].quantity, 1);
+        assert!(cards[1].foil);
+        assert_eq!(cards[1].set.as_deref(), Some("ICE"));
+    }
+
+    #[test]
+    fn rejects_deck_without_name_column() {
+        assert!(parse("Count,Edition\n1,m10\n").is_err());
+    }
+
+    #[test]
+    fn round_trips_deck() {
+        let mut bolt = DeckEntry::new(4, "Lightning Bolt", DeckSection::Main);
+        bolt.set = Some("M10".to_string());
+        bolt.collector_number = Some("146".to_string());
+        bolt.foil = true;
+        let deck = Deck::new(vec![
+            DeckEntry::new(1, "Krenko, Mob Boss", DeckSection::Commander),
+            bolt,
+            DeckEntry::new(2, "Pyroblast", DeckSection::Sideboard),
+        ]);
+
+        let output = write(&deck).unwrap();
+        assert!(output.starts_with("Count,Name,Edition,Collector Number,Foil,Board\n"));
+        assert!(output.contains("4,Lightning Bolt,m10,146,foil,mainboard\n"));
+        assert_eq!(parse(&output).unwrap(), deck.cards);
+    }
+}
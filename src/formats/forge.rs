@@ -0,0 +1,142 @@
+use anyhow::Result;
+
+use crate::card::{Deck, DeckEntry, DeckSection};
+
+/// Parses a Forge `.dck` deck
+///
+/// Entries are grouped into `[Main]` / `[Sideboard]` / `[Commander]` sections
+/// and written as `4 Lightning Bolt|M10`, optionally followed by an art index
+pub(crate) fn parse(content: &str) -> Result<Vec<DeckEntry>> {
+    let mut section = None;
+    let mut cards = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = match header.to_lowercase().as_str() {
+                "main" => Some(DeckSection::Main),
+                "sideboard" => Some(DeckSection::Sideboard),
+                "commander" => Some(DeckSection::Commander),
+                // Metadata, planes, schemes etc. aren't cards in the deck
+                _ => None,
+            };
+            continue;
+        }
+
+        let Some(section) = section else {
+            continue;
+        };
+
+        let Some((quantity, card)) = line.split_once(' ') else {
+            continue;
+        };
+
+        let quantity = quantity.parse::<u32>().unwrap_or(1);
+        let mut parts = card.split('|');
+        let name = parts.next().unwrap_or_default().trim();
+        if name.is_empty() {
+            continue;
+        }
+
+        let mut entry = DeckEntry::new(quantity, name, section);
+        entry.set = parts
+            .next()
+            .map(|set| set.trim().to_uppercase())
+            .filter(|set| !set.is_empty());
+        cards.push(entry);
+    }
+
+    Ok(cards)
+}
+
+/// Writes a deck out as a Forge `.dck` deck
+pub(crate) fn write(deck: &Deck) -> Result<String> {
+    let mut output = String::from("[metadata]\n");
+    output.push_str(&format!(
+        "Name={}\n",
+        deck.name.as_deref().unwrap_or("Deck")
+    ));
+
+    let sections = [
+        ("Commander", DeckSection::Commander),
+        ("Main", DeckSection::Main),
+        ("Sideboard", DeckSection::Sideboard),
+    ];
+
+    for (header, section) in sections {
+        let cards: Vec<&DeckEntry> = deck
+            .cards
+            .iter()
+            .filter(|card| card.section == section)
+            .collect();
+
+        if cards.is_empty() && section != DeckSection::Main {
+            continue;
+        }
+
+        output.push_str(&format!("[{header}]\n"));
+        for card in cards {
+            match &card.set {
+                Some(set) => output.push_str(&format!("{} {}|{set}\n", card.quantity, card.name)),
+                None => output.push_str(&format!("{} {}\n", card.quantity, card.name)),
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sections() {
+        let content = "[metadata]\nName=Goblins\n[Commander]\n1 Krenko, Mob Boss|DDT\n[Main]\n4 Lightning Bolt|m10|1\n10 Mountain\n[Sideboard]\n2 Pyroblast|ICE\n[Planes]\n1 Tazeem\n";
+        let cards = parse(content).unwrap();
+
+        let summary: Vec<(u32, &str, DeckSection, Option<&str>)> = cards
+            .iter()
+            .map(|card| {
+                (
+                    card.quantity,
+                    card.name.as_str(),
+                    card.section,
+                    card.set.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (1, "Krenko, Mob Boss", DeckSection::Commander, Some("DDT")),
+                (4, "Lightning Bolt", DeckSection::Main, Some("M10")),
+                (10, "Mountain", DeckSection::Main, None),
+                (2, "Pyroblast", DeckSection::Sideboard, Some("ICE")),
+            ]
+        );
+    }
+
+    #[test]
+    fn round_trips_deck() {
+        let mut bolt = DeckEntry::new(4, "Lightning Bolt", DeckSection::Main);
+        bolt.set = Some("M10".to_string());
+        let mut deck = Deck::new(vec![
+            DeckEntry::new(1, "Krenko, Mob Boss", DeckSection::Commander),
+            bolt,
+            DeckEntry::new(2, "Pyroblast", DeckSection::Sideboard),
+        ]);
+        deck.name = Some("Goblins".to_string());
+
+        let output = write(&deck).unwrap();
+        assert_eq!(
+            output,
+            "[metadata]\nName=Goblins\n[Commander]\n1 Krenko, Mob Boss\n[Main]\n4 Lightning Bolt|M10\n[Sideboard]\n2 Pyroblast\n"
+        );
+        assert_eq!(parse(&output).unwrap(), deck.cards);
+    }
+}
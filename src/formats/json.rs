@@ -0,0 +1,198 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::{
+    card::{DeckEntry, DeckSection},
+    formats::csv::{is_foil, parse_section},
+};
+
+/// Parses a JSON deck export from Moxfield or Archidekt
+pub(crate) fn parse(content: &str) -> Result<Vec<DeckEntry>> {
+    let deck: Value = serde_json::from_str(content).context("parsing json deck")?;
+
+    if deck["cards"].is_array() {
+        return parse_archidekt(&deck);
+    }
+
+    if deck["boards"].is_object() || deck["mainboard"].is_object() {
+        return parse_moxfield(&deck);
+    }
+
+    anyhow::bail!("unrecognised json deck format")
+}
+
+fn parse_moxfield(deck: &Value) -> Result<Vec<DeckEntry>> {
+    let boards = [
+        ("mainboard", DeckSection::Main),
+        ("sideboard", DeckSection::Sideboard),
+        ("commanders", DeckSection::Commander),
+        ("companions", DeckSection::Sideboard),
+    ];
+
+    let mut cards = Vec::new();
+    for (board, section) in boards {
+        // Newer exports nest each board under `boards.<board>.cards`
+        let entries = if deck["boards"].is_object() {
+            &deck["boards"][board]["cards"]
+        } else {
+            &deck[board]
+        };
+
+        let Some(entries) = entries.as_object() else {
+            continue;
+        };
+
+        for (key, entry) in entries {
+            let card = &entry["card"];
+            let name = card["name"].as_str().unwrap_or(key);
+            let quantity = entry["quantity"].as_u64().unwrap_or(1) as u32;
+
+            let mut deck_entry = DeckEntry::new(quantity, name, section);
+            deck_entry.set = card["set"].as_str().map(|set| set.to_uppercase());
+            deck_entry.collector_number = card["cn"].as_str().map(|cn| cn.to_string());
+            deck_entry.foil = entry["isFoil"].as_bool().unwrap_or_default()
+                || entry["finish"].as_str().is_some_and(is_foil);
+            deck_entry.mtgo_id = card["mtgo_id"].as_u64().map(|id| id as u32);
+            cards.push(deck_entry);
+        }
+    }
+
+    Ok(cards)
+}
+
+fn parse_archidekt(deck: &Value) -> Result<Vec<DeckEntry>> {
+    let mut cards = Vec::new();
+    for entry in deck["cards"].as_array().into_iter().flatten() {
+        let card = &entry["card"];
+        let Some(name) = card["oracleCard"]["name"]
+            .as_str()
+            .or_else(|| card["name"].as_str())
+        else {
+            continue;
+        };
+
+        let categories = entry["categories"].as_array().map(|categories| {
+            categories
+                .iter()
+                .filter_map(|category| category.as_str())
+                .collect::<Vec<&str>>()
+                .join(",")
+        });
+
+        let Some(section) = parse_section(categories.as_deref().filter(|c| !c.is_empty())) else {
+            continue;
+        };
+
+        let quantity = entry["quantity"].as_u64().unwrap_or(1) as u32;
+        let mut deck_entry = DeckEntry::new(quantity, name, section);
+        deck_entry.set = card["edition"]["editioncode"]
+            .as_str()
+            .map(|set| set.to_uppercase());
+        deck_entry.collector_number = card["collectorNumber"].as_str().map(|cn| cn.to_string());
+        deck_entry.foil = entry["modifier"].as_str().is_some_and(is_foil);
+        deck_entry.mtgo_id = card["mtgoId"].as_u64().map(|id| id as u32);
+        cards.push(deck_entry);
+    }
+
+    Ok(cards)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_moxfield_export() {
+        let content = r#"{
+            "name": "Goblins",
+            "boards": {
+                "mainboard": {
+                    "cards": {
+                        "bolt": {
+                            "quantity": 4,
+                            "finish": "foil",
+                            "card": { "name": "Lightning Bolt", "set": "m10", "cn": "146", "mtgo_id": 46744 }
+                        }
+                    }
+                },
+                "sideboard": { "cards": {} },
+                "commanders": {
+                    "cards": {
+                        "krenko": { "quantity": 1, "card": { "name": "Krenko, Mob Boss" } }
+                    }
+                },
+                "maybeboard": {
+                    "cards": {
+                        "moon": { "quantity": 1, "card": { "name": "Blood Moon" } }
+                    }
+                }
+            }
+        }"#;
+        let cards = parse(content).unwrap();
+
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].quantity, 4);
+        assert_eq!(cards[0].name, "Lightning Bolt");
+        assert_eq!(cards[0].section, DeckSection::Main);
+        assert_eq!(cards[0].set.as_deref(), Some("M10"));
+        assert_eq!(cards[0].collector_number.as_deref(), Some("146"));
+        assert_eq!(cards[0].mtgo_id, Some(46744));
+        assert!(cards[0].foil);
+        assert_eq!(cards[1].name, "Krenko, Mob Boss");
+        assert_eq!(cards[1].section, DeckSection::Commander);
+    }
+
+    #[test]
+    fn parses_legacy_moxfield_export() {
+        let content = r#"{
+            "mainboard": { "Opt": { "quantity": 2, "isFoil": true, "card": { "name": "Opt" } } },
+            "sideboard": { "Negate": { "quantity": 1, "card": { "name": "Negate" } } }
+        }"#;
+        let cards = parse(content).unwrap();
+
+        assert_eq!(cards.len(), 2);
+        assert!(cards[0].foil);
+        assert_eq!(cards[1].section, DeckSection::Sideboard);
+    }
+
+    #[test]
+    fn parses_archidekt_export() {
+        let content = r#"{
+            "name": "Goblins",
+            "cards": [
+                {
+                    "quantity": 1,
+                    "modifier": "Normal",
+                    "categories": ["Commander"],
+                    "card": { "oracleCard": { "name": "Krenko, Mob Boss" }, "edition": { "editioncode": "ddt" } }
+                },
+                {
+                    "quantity": 4,
+                    "modifier": "Etched",
+                    "categories": ["Burn", "Instant"],
+                    "card": { "oracleCard": { "name": "Lightning Bolt" }, "collectorNumber": "146", "mtgoId": 46744 }
+                },
+                {
+                    "quantity": 1,
+                    "categories": ["Maybeboard"],
+                    "card": { "oracleCard": { "name": "Blood Moon" } }
+                }
+            ]
+        }"#;
+        let cards = parse(content).unwrap();
+
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].section, DeckSection::Commander);
+        assert_eq!(cards[0].set.as_deref(), Some("DDT"));
+        assert_eq!(cards[1].quantity, 4);
+        assert_eq!(cards[1].section, DeckSection::Main);
+        assert_eq!(cards[1].collector_number.as_deref(), Some("146"));
+        assert_eq!(cards[1].mtgo_id, Some(46744));
+        assert!(!cards[1].foil);
+    }
+
+    #[test]
+    fn rejects_unknown_json() {
+        assert!(parse(r#"{ "deck": [] }"#).is_err());
+    }
+}
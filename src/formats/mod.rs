@@ -0,0 +1,106 @@
+pub(crate) mod cockatrice;
+pub(crate) mod csv;
+pub(crate) mod forge;
+pub(crate) mod json;
+pub(crate) mod mtgo;
+pub(crate) mod text;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::path::Path;
+use tokio::fs;
+
+use crate::card::Deck;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub(crate) enum DeckFormat {
+    /// Plain text deck list
+    Text,
+    /// MTGA deck list with set codes and collector numbers
+    Mtga,
+    /// MTGO `.dek` XML
+    Mtgo,
+    /// Cockatrice `.cod` XML
+    Cockatrice,
+    /// Forge `.dck`
+    Forge,
+    /// Moxfield / Archidekt / Deckstats CSV export
+    Csv,
+    /// Moxfield / Archidekt JSON export
+    Json,
+}
+
+impl DeckFormat {
+    /// Guesses the deck format from the file extension, defaulting to text
+    pub(crate) fn from_path(path: impl AsRef<Path>) -> Self {
+        let extension = path
+            .as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase());
+
+        match extension.as_deref() {
+            Some("dek") => Self::Mtgo,
+            Some("cod") => Self::Cockatrice,
+            Some("dck") => Self::Forge,
+            Some("csv") => Self::Csv,
+            Some("json") => Self::Json,
+            _ => Self::Text,
+        }
+    }
+}
+
+pub(crate) async fn load_deck(deck: impl AsRef<Path>, format: Option<DeckFormat>) -> Result<Deck> {
+    let deck = deck.as_ref();
+    let format = format.unwrap_or_else(|| DeckFormat::from_path(deck));
+    let content = fs::read_to_string(deck)
+        .await
+        .context("loading deck file")?;
+
+    let cards = match format {
+        // MTGA lists are plain text with optional set codes / collector numbers
+        DeckFormat::Text | DeckFormat::Mtga => text::parse(&content)?,
+        DeckFormat::Mtgo => mtgo::parse(&content)?,
+        DeckFormat::Cockatrice => cockatrice::parse(&content)?,
+        DeckFormat::Forge => forge::parse(&content)?,
+        DeckFormat::Csv => csv::parse(&content)?,
+        DeckFormat::Json => json::parse(&content)?,
+    };
+
+    let mut loaded_deck = Deck::new(cards);
+    loaded_deck.name = deck
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string());
+
+    Ok(loaded_deck)
+}
+
+pub(crate) fn write_deck(deck: &Deck, format: DeckFormat) -> Result<String> {
+    let content = match format {
+        DeckFormat::Text => text::write(deck)?,
+        DeckFormat::Mtga => text::write_mtga(deck)?,
+        DeckFormat::Mtgo => mtgo::write(deck)?,
+        DeckFormat::Cockatrice => cockatrice::write(deck)?,
+        DeckFormat::Forge => forge::write(deck)?,
+        DeckFormat::Csv => csv::write(deck)?,
+        DeckFormat::Json => anyhow::bail!("writing {format:?} decks is not supported"),
+    };
+
+    Ok(content)
+}
+
+pub(crate) async fn save_deck(
+    deck: &Deck,
+    output: impl AsRef<Path>,
+    format: Option<DeckFormat>,
+) -> Result<()> {
+    let output = output.as_ref();
+    let format = format.unwrap_or_else(|| DeckFormat::from_path(output));
+    let content = write_deck(deck, format)?;
+
+    fs::write(output, content)
+        .await
+        .context("writing deck file")?;
+
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::card::{Deck, DeckEntry, DeckSection};
+
+const XML_HEADER: &str = r#"<?xml version="1.0" encoding="utf-8"?>"#;
+const XSD_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema";
+const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename = "Deck")]
+struct MtgoDeck {
+    #[serde(rename = "@xmlns:xsd", default, skip_deserializing)]
+    xsd: String,
+    #[serde(rename = "@xmlns:xsi", default, skip_deserializing)]
+    xsi: String,
+    #[serde(rename = "NetDeckID", default)]
+    net_deck_id: u32,
+    #[serde(rename = "PreconstructedDeckID", default)]
+    preconstructed_deck_id: u32,
+    #[serde(rename = "Cards", default)]
+    cards: Vec<MtgoCard>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MtgoCard {
+    #[serde(rename = "@CatID", default)]
+    cat_id: u32,
+    #[serde(rename = "@Quantity")]
+    quantity: u32,
+    #[serde(rename = "@Sideboard", default)]
+    sideboard: bool,
+    #[serde(rename = "@Name")]
+    name: String,
+    #[serde(rename = "@Annotation", default)]
+    annotation: u32,
+    /// Marks commanders, which MTGO itself only lists in the sideboard
+    #[serde(
+        rename = "@Commander",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    commander: Option<bool>,
+}
+
+/// Parses an MTGO `.dek` XML deck
+pub(crate) fn parse(content: &str) -> Result<Vec<DeckEntry>> {
+    let deck: MtgoDeck = quick_xml::de::from_str(content).context("parsing mtgo deck")?;
+
+    let cards = deck
+        .cards
+        .into_iter()
+        .map(|card| {
+            let section = if card.commander == Some(true) {
+                DeckSection::Commander
+            } else if card.sideboard {
+                DeckSection::Sideboard
+            } else {
+                DeckSection::Main
+            };
+
+            let mut entry = DeckEntry::new(card.quantity, card.name, section);
+            if card.cat_id != 0 {
+                entry.mtgo_id = Some(card.cat_id);
+            }
+
+            entry
+        })
+        .collect();
+
+    Ok(cards)
+}
+
+/// Writes a deck out as an MTGO `.dek` XML deck
+pub(crate) fn write(deck: &Deck) -> Result<String> {
+    let cards = deck
+        .cards
+        .iter()
+        .map(|card| MtgoCard {
+            cat_id: card.mtgo_id.unwrap_or_default(),
+            quantity: card.quantity,
+            // MTGO expects commanders to be placed in the sideboard
+            sideboard: card.section != DeckSection::Main,
+            name: card.name.clone(),
+            annotation: 0,
+            commander: (card.section == DeckSection::Commander).then_some(true),
+        })
+        .collect();
+
+    let mtgo_deck = MtgoDeck {
+        xsd: XSD_NAMESPACE.to_string(),
+        xsi: XSI_NAMESPACE.to_string(),
+        cards,
+        ..Default::default()
+    };
+
+    let mut output = String::new();
+    let mut serializer = quick_xml::se::Serializer::new(&mut output);
+    serializer.indent(' ', 2);
+    mtgo_deck
+        .serialize(serializer)
+        .context("serialising mtgo deck")?;
+
+    Ok(format!("{XML_HEADER}\n{output}\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DECK: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<Deck xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <NetDeckID>0</NetDeckID>
+  <PreconstructedDeckID>0</PreconstructedDeckID>
+  <Cards CatID="46744" Quantity="4" Sideboard="false" Name="Lightning Bolt" Annotation="0" />
+  <Cards CatID="0" Quantity="2" Sideboard="true" Name="Pyroblast" Annotation="0" />
+  <Cards Quantity="10" Sideboard="false" Name="Mountain" />
+</Deck>
+"#;
+
+    #[test]
+    fn parses_sideboard_and_cat_ids() {
+        let cards = parse(DECK).unwrap();
+
+        assert_eq!(cards.len(), 3);
+        assert_eq!(cards[0].quantity, 4);
+        assert_eq!(cards[0].name, "Lightning Bolt");
+        assert_eq!(cards[0].section, DeckSection::Main);
+        assert_eq!(cards[0].mtgo_id, Some(46744));
+        assert_eq!(cards[1].section, DeckSection::Sideboard);
+        assert_eq!(cards[1].mtgo_id, None);
+        assert_eq!(cards[2].quantity, 10);
+    }
+
+    #[test]
+    fn round_trips_deck() {
+        let mut commander = DeckEntry::new(1, "Krenko, Mob Boss", DeckSection::Commander);
+        commander.mtgo_id = Some(48284);
+        let deck = Deck::new(vec![
+            commander,
+            DeckEntry::new(1, "Goblin Chieftain", DeckSection::Main),
+            DeckEntry::new(1, "Pyroblast", DeckSection::Sideboard),
+        ]);
+
+        let output = write(&deck).unwrap();
+        assert!(output.contains(r#"Sideboard="true" Name="Krenko, Mob Boss""#));
+        assert_eq!(parse(&output).unwrap(), deck.cards);
+
+        let cards = parse(DECK).unwrap();
+        let output = write(&Deck::new(cards.clone())).unwrap();
+        assert_eq!(parse(&output).unwrap(), cards);
+    }
+}
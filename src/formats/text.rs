@@ -0,0 +1,261 @@
+use anyhow::Result;
+
+use crate::card::{Deck, DeckEntry, DeckSection};
+
+const FOIL_MARKER: &str = "*F*";
+
+/// Parses a plain text / MTGA style deck list
+pub(crate) fn parse(content: &str) -> Result<Vec<DeckEntry>> {
+    let mut section = Some(DeckSection::Main);
+    let mut cards = Vec::new();
+
+    for (idx, line) in content.lines().enumerate() {
+        let line = line.trim();
+        match line {
+            "Deck" => {
+                section = Some(DeckSection::Main);
+                continue;
+            }
+            "Sideboard" => {
+                section = Some(DeckSection::Sideboard);
+                continue;
+            }
+            "Commander" => {
+                section = Some(DeckSection::Commander);
+                continue;
+            }
+            // A companion starts outside the deck, like the sideboard it's kept in
+            "Companion" => {
+                section = Some(DeckSection::Sideboard);
+                continue;
+            }
+            // MTGA's `About` block holds deck metadata (`Name My Deck`), not cards
+            "About" => {
+                section = None;
+                continue;
+            }
+            _ => {}
+        }
+
+        let Some(section) = section else {
+            continue;
+        };
+
+        let (entry, entry_section) = trim_entry(line, section);
+        if !is_valid_deck_entry(&entry) {
+            continue;
+        }
+
+        // `4 Lightning Bolt` or `4x Lightning Bolt`
+        let quantity = entry.split_once(' ').and_then(|(quantity, name)| {
+            Some((quantity.trim_end_matches('x').parse().ok()?, name))
+        });
+        let Some((quantity, name)) = quantity else {
+            anyhow::bail!("line {}: no card quantity in '{line}'", idx + 1);
+        };
+
+        let (name, foil) = match name.trim().strip_suffix(FOIL_MARKER) {
+            Some(name) => (name.trim(), true),
+            None => (name.trim(), false),
+        };
+
+        let (name, set, collector_number) = split_printing(name);
+        let mut card = DeckEntry::new(quantity, name, entry_section);
+        card.set = set;
+        card.collector_number = collector_number;
+        card.foil = foil;
+        cards.push(card);
+    }
+
+    Ok(cards)
+}
+
+/// Writes a deck out as a plain text deck list
+pub(crate) fn write(deck: &Deck) -> Result<String> {
+    let mut output = String::new();
+    for card in deck.cards.iter() {
+        output.push_str(&write_entry(card));
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
+/// Writes a single deck entry as a plain text deck list line
+pub(crate) fn write_entry(card: &DeckEntry) -> String {
+    let mut entry = format!("{} {}", card.quantity, card.name);
+    if card.foil {
+        entry.push_str(&format!(" {FOIL_MARKER}"));
+    }
+
+    match card.section {
+        DeckSection::Main => entry,
+        DeckSection::Sideboard => format!("SB: {entry}"),
+        DeckSection::Commander => format!("{entry} # !Commander"),
+    }
+}
+
+/// Writes a deck out as an MTGA deck list (`4 Lightning Bolt (M10) 146`)
+pub(crate) fn write_mtga(deck: &Deck) -> Result<String> {
+    let sections = [
+        ("Commander", DeckSection::Commander),
+        ("Deck", DeckSection::Main),
+        ("Sideboard", DeckSection::Sideboard),
+    ];
+
+    let mut blocks = Vec::new();
+    for (header, section) in sections {
+        let mut block = String::new();
+        for card in deck.cards.iter().filter(|card| card.section == section) {
+            block.push_str(&format!("{} {}", card.quantity, card.name));
+            if let Some(set) = &card.set {
+                block.push_str(&format!(" ({set})"));
+                if let Some(collector_number) = &card.collector_number {
+                    block.push_str(&format!(" {collector_number}"));
+                }
+            }
+            block.push('\n');
+        }
+
+        if !block.is_empty() {
+            blocks.push(format!("{header}\n{block}"));
+        }
+    }
+
+    Ok(blocks.join("\n"))
+}
+
+/// Splits an MTGA style `Name (SET) 123` entry into its name, set code
+/// and collector number
+fn split_printing(name: &str) -> (&str, Option<String>, Option<String>) {
+    if let Some((card, printing)) = name.rsplit_once(" (") {
+        if let Some((set, collector_number)) = printing.split_once(')') {
+            if !set.is_empty() && !set.contains(' ') {
+                let collector_number = collector_number.trim();
+                let collector_number =
+                    (!collector_number.is_empty()).then(|| collector_number.to_string());
+
+                return (card.trim(), Some(set.to_uppercase()), collector_number);
+            }
+        }
+    }
+
+    (name, None, None)
+}
+
+fn is_valid_deck_entry(card: &str) -> bool {
+    if card.is_empty() || card.starts_with("//") {
+        return false;
+    }
+
+    true
+}
+
+fn trim_entry(mut entry: &str, section: DeckSection) -> (String, DeckSection) {
+    let mut section = section;
+    entry = entry.trim();
+    if let Some(removed_sb) = entry.strip_prefix("SB: ") {
+        entry = removed_sb;
+        section = DeckSection::Sideboard;
+    }
+
+    if let Some(removed_commander) = entry.strip_suffix("# !Commander") {
+        entry = removed_commander;
+        section = DeckSection::Commander;
+    }
+
+    entry = entry.trim();
+    (entry.to_string(), section)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MTGA_DECK: &str = "About
+Name Goblins
+
+Commander
+1 Krenko, Mob Boss (DDT) 15
+
+Companion
+1 Lurrus of the Dream-Den (IKO) 226
+
+Deck
+4 Lightning Bolt (M10) 146
+2 Goblin Guide (2X2) 117 *F*
+10 Mountain
+
+Sideboard
+2 Pyroblast (ICE) 213
+";
+
+    #[test]
+    fn parses_mtga_deck() {
+        let cards = parse(MTGA_DECK).unwrap();
+
+        let summary: Vec<(u32, &str, DeckSection)> = cards
+            .iter()
+            .map(|card| (card.quantity, card.name.as_str(), card.section))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (1, "Krenko, Mob Boss", DeckSection::Commander),
+                (1, "Lurrus of the Dream-Den", DeckSection::Sideboard),
+                (4, "Lightning Bolt", DeckSection::Main),
+                (2, "Goblin Guide", DeckSection::Main),
+                (10, "Mountain", DeckSection::Main),
+                (2, "Pyroblast", DeckSection::Sideboard),
+            ]
+        );
+        assert_eq!(cards[2].set.as_deref(), Some("M10"));
+        assert_eq!(cards[2].collector_number.as_deref(), Some("146"));
+        assert!(cards[3].foil);
+        assert_eq!(cards[4].set, None);
+    }
+
+    #[test]
+    fn parses_plain_text_deck() {
+        let content =
+            "// Burn\n4x Lightning Bolt\n1 Krenko, Mob Boss # !Commander\nSB: 2 Pyroblast\n";
+        let cards = parse(content).unwrap();
+
+        assert_eq!(cards.len(), 3);
+        assert_eq!(cards[0].quantity, 4);
+        assert_eq!(cards[0].name, "Lightning Bolt");
+        assert_eq!(cards[1].name, "Krenko, Mob Boss");
+        assert_eq!(cards[1].section, DeckSection::Commander);
+        assert_eq!(cards[2].section, DeckSection::Sideboard);
+    }
+
+    #[test]
+    fn rejects_entries_without_quantity() {
+        let error = parse("4 Lightning Bolt\nGoblin Guide\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2: no card quantity in 'Goblin Guide'"
+        );
+        assert!(parse("Lightning Bolt\n").is_err());
+    }
+
+    #[test]
+    fn round_trips_mtga_deck() {
+        let cards = parse(MTGA_DECK).unwrap();
+        let output = write_mtga(&Deck::new(cards.clone())).unwrap();
+
+        // The companion is written back out with the rest of the sideboard
+        let written = parse(&output).unwrap();
+        assert_eq!(written.len(), cards.len());
+        for card in cards.iter() {
+            let matching = written
+                .iter()
+                .find(|written| written.name == card.name)
+                .unwrap();
+            assert_eq!(
+                (matching.quantity, matching.section, &matching.set),
+                (card.quantity, card.section, &card.set)
+            );
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use strsim::normalized_damerau_levenshtein;
+
+use crate::utils::normalise_name;
+
+/// Minimum similarity for a card name to be offered as a suggestion
+const SUGGESTION_THRESHOLD: f64 = 0.7;
+/// Minimum similarity for a suggestion to be accepted automatically with `--fix`
+const HIGH_CONFIDENCE_THRESHOLD: f64 = 0.85;
+const MAX_SUGGESTIONS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Suggestion {
+    pub(crate) name: String,
+    pub(crate) score: f64,
+}
+
+/// Ranks card names by edit distance to find likely matches for typos
+#[derive(Debug, Clone)]
+pub(crate) struct FuzzyMatcher {
+    /// Card names with their normalised form and its length in chars, sorted by length
+    names: Vec<(String, String, usize)>,
+}
+
+impl FuzzyMatcher {
+    pub(crate) fn new(names: Vec<String>) -> Self {
+        let mut names: Vec<(String, String, usize)> = names
+            .into_iter()
+            .map(|name| {
+                let normalised = normalise_name(&name);
+                let length = normalised.chars().count();
+                (name, normalised, length)
+            })
+            .collect();
+
+        names.sort_by_key(|(_, _, length)| *length);
+        Self { names }
+    }
+
+    /// Names that could reach the suggestion threshold. The edit distance is at
+    /// least the difference in length, so the similarity can't be above the
+    /// ratio of the shorter length to the longer one. Only names within that
+    /// ratio of `length` are scored, rounded outwards so none are missed
+    fn candidates(&self, length: usize) -> &[(String, String, usize)] {
+        let shortest = (length as f64 * SUGGESTION_THRESHOLD).floor() as usize;
+        let longest = (length as f64 / SUGGESTION_THRESHOLD).ceil() as usize;
+        let start = self.names.partition_point(|(_, _, l)| *l < shortest);
+        let end = self.names.partition_point(|(_, _, l)| *l <= longest);
+        &self.names[start..end.max(start)]
+    }
+
+    pub(crate) fn suggestions(&self, name: &str) -> Vec<Suggestion> {
+        let name = normalise_name(name);
+        let mut suggestions: Vec<Suggestion> = self
+            .candidates(name.chars().count())
+            .iter()
+            .filter_map(|(card, normalised, _)| {
+                let score = normalized_damerau_levenshtein(&name, normalised);
+                (score >= SUGGESTION_THRESHOLD).then(|| Suggestion {
+                    name: card.clone(),
+                    score,
+                })
+            })
+            .collect();
+
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score));
+        suggestions.truncate(MAX_SUGGESTIONS);
+        suggestions
+    }
+
+    /// Returns the best suggestion if it is the only high confidence match
+    pub(crate) fn unique_match(suggestions: &[Suggestion]) -> Option<&Suggestion> {
+        let mut confident = suggestions
+            .iter()
+            .filter(|suggestion| suggestion.score >= HIGH_CONFIDENCE_THRESHOLD);
+
+        match (confident.next(), confident.next()) {
+            (Some(suggestion), None) => Some(suggestion),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn format_suggestions(suggestions: &[Suggestion]) -> String {
+    if suggestions.is_empty() {
+        return String::new();
+    }
+
+    let names: Vec<&str> = suggestions.iter().map(|s| s.name.as_str()).collect();
+    format!(" (did you mean: {}?)", names.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher() -> FuzzyMatcher {
+        let names = [
+            "Lightning Bolt",
+            "Lightning Helix",
+            "Counterspell",
+            "Opt",
+            "Ponder",
+        ];
+        FuzzyMatcher::new(names.iter().map(|name| name.to_string()).collect())
+    }
+
+    #[test]
+    fn suggests_close_names() {
+        let suggestions = matcher().suggestions("lightnig bolt");
+        assert_eq!(suggestions[0].name, "Lightning Bolt");
+        assert!(FuzzyMatcher::unique_match(&suggestions).is_some());
+    }
+
+    #[test]
+    fn skips_names_too_different_in_length() {
+        assert!(matcher().suggestions("Op").is_empty());
+        assert!(matcher().candidates(3).iter().all(|(_, _, l)| *l <= 5));
+    }
+
+    #[test]
+    fn length_filter_keeps_every_suggestion() {
+        let matcher = matcher();
+        for name in ["Countrspell", "Pondre", "Lightning Hex", "Opts"] {
+            let name = normalise_name(name);
+            let expected = matcher
+                .names
+                .iter()
+                .filter(|(_, n, _)| {
+                    normalized_damerau_levenshtein(&name, n) >= SUGGESTION_THRESHOLD
+                })
+                .count();
+            assert_eq!(matcher.suggestions(&name).len(), expected);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use sqlx::FromRow;
+
+use crate::{card::Currency, utils::normalise_name};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(rename_all = "snake_case")]
+pub(crate) enum Transaction {
+    Purchase,
+    Sale,
+}
+
+/// A purchase or sale of copies of a card, with the total amount paid or received
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub(crate) struct LedgerEntry {
+    pub(crate) id: i64,
+    pub(crate) name: String,
+    pub(crate) kind: Transaction,
+    pub(crate) quantity: u32,
+    pub(crate) amount: f32,
+    pub(crate) currency: Currency,
+    pub(crate) date: String,
+}
+
+impl std::fmt::Display for LedgerEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = match self.kind {
+            Transaction::Purchase => "bought",
+            Transaction::Sale => "sold",
+        };
+
+        write!(
+            f,
+            "#{} {} {action} {}x {} for {}",
+            self.id,
+            self.date,
+            self.quantity,
+            self.name,
+            self.currency.to_price(Some(self.amount))
+        )
+    }
+}
+
+/// Purchases and sales of a card in one currency
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Holding {
+    pub(crate) name: String,
+    pub(crate) currency: Currency,
+    pub(crate) bought: u32,
+    pub(crate) cost: f32,
+    pub(crate) sold: u32,
+    pub(crate) proceeds: f32,
+}
+
+impl Holding {
+    /// Copies still held, assuming sales are of copies that were recorded as bought
+    pub(crate) fn held(&self) -> u32 {
+        self.bought.saturating_sub(self.sold)
+    }
+
+    pub(crate) fn average_cost(&self) -> f32 {
+        match self.bought {
+            0 => 0.0,
+            bought => self.cost / bought as f32,
+        }
+    }
+
+    /// What the copies still held cost, at the average cost per copy
+    pub(crate) fn cost_of_held(&self) -> f32 {
+        self.average_cost() * self.held() as f32
+    }
+
+    /// Copies sold beyond the copies recorded as bought
+    pub(crate) fn oversold(&self) -> u32 {
+        self.sold.saturating_sub(self.bought)
+    }
+
+    /// Gain on the copies sold, against the average cost per copy. Only sales of
+    /// copies recorded as bought are counted, at the average sale price, as what
+    /// any others cost isn't known
+    pub(crate) fn realised(&self) -> f32 {
+        if self.sold == 0 {
+            return 0.0;
+        }
+
+        let sold = self.sold.min(self.bought) as f32;
+        self.proceeds / self.sold as f32 * sold - self.average_cost() * sold
+    }
+}
+
+/// Groups ledger entries into holdings per card and currency, only including
+/// entries in the same money as `currency` (e.g. euro and euro foil)
+pub(crate) fn holdings(entries: &[LedgerEntry], currency: Currency) -> Vec<Holding> {
+    let mut holdings: Vec<(String, Holding)> = Vec::new();
+    for entry in entries
+        .iter()
+        .filter(|entry| entry.currency.base() == currency.base())
+    {
+        let key = normalise_name(&entry.name);
+        let position = holdings
+            .iter()
+            .position(|(k, holding)| *k == key && holding.currency == entry.currency);
+
+        let holding = match position {
+            Some(position) => &mut holdings[position].1,
+            None => {
+                let holding = Holding {
+                    name: entry.name.clone(),
+                    currency: entry.currency,
+                    bought: 0,
+                    cost: 0.0,
+                    sold: 0,
+                    proceeds: 0.0,
+                };
+
+                holdings.push((key, holding));
+                &mut holdings.last_mut().unwrap().1
+            }
+        };
+
+        match entry.kind {
+            Transaction::Purchase => {
+                holding.bought += entry.quantity;
+                holding.cost += entry.amount;
+            }
+            Transaction::Sale => {
+                holding.sold += entry.quantity;
+                holding.proceeds += entry.amount;
+            }
+        }
+    }
+
+    holdings.into_iter().map(|(_, holding)| holding).collect()
+}
+
+/// Formats a gain or loss with its sign, e.g. `+1.50€` or `-0.20€`
+pub(crate) fn format_gain(currency: Currency, amount: f32) -> String {
+    let sign = if amount < 0.0 { "-" } else { "+" };
+    format!("{sign}{}", currency.base().to_price(Some(amount.abs())))
+}
+
+/// Checks a date is a real date given as `YYYY-MM-DD`, for sorting ledger entries
+pub(crate) fn parse_date(date: &str) -> Result<String, String> {
+    let is_valid = date.len() == 10
+        && date.char_indices().all(|(i, c)| match i {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        });
+
+    if !is_valid {
+        return Err(format!("'{date}' isn't a date in the format YYYY-MM-DD"));
+    }
+
+    let year: u32 = date[..4].parse().unwrap_or_default();
+    let month: u32 = date[5..7].parse().unwrap_or_default();
+    let day: u32 = date[8..].parse().unwrap_or_default();
+    // Every fourth year, except centuries that aren't a multiple of 400
+    let is_leap_year = matches!((year % 4, year % 100, year % 400), (0, 1.., _) | (_, _, 0));
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year => 29,
+        2 => 28,
+        _ => return Err(format!("'{date}' has no month {month}")),
+    };
+
+    if day == 0 || day > days {
+        return Err(format!("'{date}' has no day {day}"));
+    }
+
+    Ok(date.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holding(bought: u32, cost: f32, sold: u32, proceeds: f32) -> Holding {
+        Holding {
+            name: "Lightning Bolt".to_string(),
+            currency: Currency::Euro,
+            bought,
+            cost,
+            sold,
+            proceeds,
+        }
+    }
+
+    #[test]
+    fn checks_dates_exist() {
+        for date in ["2024-01-31", "2024-02-29", "2000-02-29", "2023-12-01"] {
+            assert_eq!(parse_date(date), Ok(date.to_string()));
+        }
+
+        for date in [
+            "2024-13-45",
+            "2024-00-10",
+            "2024-04-31",
+            "2023-02-29",
+            "1900-02-29",
+        ] {
+            assert!(parse_date(date).is_err(), "accepted {date}");
+        }
+
+        for date in ["2024-1-01", "24-01-01", "2024/01/01", "today"] {
+            assert!(parse_date(date).is_err(), "accepted {date}");
+        }
+    }
+
+    #[test]
+    fn realises_gains_on_copies_bought() {
+        let sold_some = holding(4, 8.0, 1, 5.0);
+        assert_eq!(sold_some.held(), 3);
+        assert_eq!(sold_some.realised(), 3.0);
+        assert_eq!(sold_some.oversold(), 0);
+
+        // Only the two copies recorded as bought count, at 3 each
+        let oversold = holding(2, 2.0, 4, 12.0);
+        assert_eq!(oversold.held(), 0);
+        assert_eq!(oversold.realised(), 4.0);
+        assert_eq!(oversold.oversold(), 2);
+
+        let never_bought = holding(0, 0.0, 1, 10.0);
+        assert_eq!(never_bought.realised(), 0.0);
+        assert_eq!(never_bought.oversold(), 1);
+    }
+}
@@ -0,0 +1,636 @@
+use clap::ValueEnum;
+use std::collections::HashMap;
+
+use crate::{
+    card::{DbCard, Deck, DeckEntry, DeckSection},
+    commander::{check_commander, is_companion},
+    formats::text,
+    utils::{format_candidates, is_basic_land, normalise_name},
+};
+
+/// Formats that decks can be checked against, named after Scryfall's legality keys
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Format {
+    Standard,
+    Pioneer,
+    Modern,
+    Legacy,
+    Vintage,
+    Pauper,
+    Historic,
+    Explorer,
+    Timeless,
+    Alchemy,
+    Premodern,
+    Oldschool,
+    Penny,
+    Commander,
+    Duel,
+    #[clap(name = "paupercommander")]
+    PauperCommander,
+    Predh,
+    Oathbreaker,
+    Brawl,
+    #[clap(name = "standardbrawl")]
+    StandardBrawl,
+    Gladiator,
+}
+
+/// Deck construction rules for a format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FormatRules {
+    pub(crate) min_deck_size: u32,
+    pub(crate) max_deck_size: Option<u32>,
+    pub(crate) max_sideboard: u32,
+    pub(crate) copy_limit: u32,
+}
+
+impl Format {
+    /// Formats shown in the legality matrix unless all formats are requested
+    pub(crate) const MAIN: [Format; 7] = [
+        Self::Standard,
+        Self::Pioneer,
+        Self::Modern,
+        Self::Legacy,
+        Self::Vintage,
+        Self::Pauper,
+        Self::Commander,
+    ];
+
+    /// Key used for this format in Scryfall's `legalities` object
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            Self::Standard => "standard",
+            Self::Pioneer => "pioneer",
+            Self::Modern => "modern",
+            Self::Legacy => "legacy",
+            Self::Vintage => "vintage",
+            Self::Pauper => "pauper",
+            Self::Historic => "historic",
+            Self::Explorer => "explorer",
+            Self::Timeless => "timeless",
+            Self::Alchemy => "alchemy",
+            Self::Premodern => "premodern",
+            Self::Oldschool => "oldschool",
+            Self::Penny => "penny",
+            Self::Commander => "commander",
+            Self::Duel => "duel",
+            Self::PauperCommander => "paupercommander",
+            Self::Predh => "predh",
+            Self::Oathbreaker => "oathbreaker",
+            Self::Brawl => "brawl",
+            Self::StandardBrawl => "standardbrawl",
+            Self::Gladiator => "gladiator",
+        }
+    }
+
+    /// Whether decks in this format are led by a commander in the command zone
+    pub(crate) fn has_commander(self) -> bool {
+        matches!(
+            self,
+            Self::Commander
+                | Self::Duel
+                | Self::PauperCommander
+                | Self::Predh
+                | Self::Oathbreaker
+                | Self::Brawl
+                | Self::StandardBrawl
+        )
+    }
+
+    pub(crate) fn rules(self) -> FormatRules {
+        let (min_deck_size, max_deck_size, max_sideboard, copy_limit) = match self {
+            Self::Commander
+            | Self::Duel
+            | Self::PauperCommander
+            | Self::Predh
+            | Self::Brawl
+            | Self::Gladiator => (100, Some(100), 0, 1),
+            Self::Oathbreaker | Self::StandardBrawl => (60, Some(60), 0, 1),
+            _ => (60, None, 15, 4),
+        };
+
+        FormatRules {
+            min_deck_size,
+            max_deck_size,
+            max_sideboard,
+            copy_limit,
+        }
+    }
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Oldschool => "Old School",
+            Self::PauperCommander => "Pauper Commander",
+            Self::Predh => "PreDH",
+            Self::StandardBrawl => "Standard Brawl",
+            _ => return write!(f, "{self:?}"),
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Legality {
+    Legal,
+    Restricted,
+    Banned,
+    NotLegal,
+}
+
+impl Legality {
+    /// Reads a card's legality in a format from its stored legalities JSON,
+    /// treating missing entries as not legal
+    pub(crate) fn of(card: &DbCard, format: Format) -> Self {
+        let legalities: HashMap<String, String> = card
+            .legalities
+            .as_deref()
+            .and_then(|legalities| serde_json::from_str(legalities).ok())
+            .unwrap_or_default();
+
+        match legalities.get(format.key()).map(|l| l.as_str()) {
+            Some("legal") => Self::Legal,
+            Some("restricted") => Self::Restricted,
+            Some("banned") => Self::Banned,
+            _ => Self::NotLegal,
+        }
+    }
+}
+
+impl std::fmt::Display for Legality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let legality = match self {
+            Self::Legal => "legal",
+            Self::Restricted => "restricted",
+            Self::Banned => "banned",
+            Self::NotLegal => "-",
+        };
+
+        write!(f, "{legality}")
+    }
+}
+
+/// A rule broken by a deck, with the deck lines responsible for it
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Violation {
+    pub(crate) message: String,
+    pub(crate) lines: Vec<String>,
+}
+
+impl Violation {
+    pub(crate) fn new(message: impl Into<String>, entries: &[&DeckEntry]) -> Self {
+        Self {
+            message: message.into(),
+            lines: entries
+                .iter()
+                .map(|entry| text::write_entry(entry))
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        if !self.lines.is_empty() {
+            write!(f, ": {}", self.lines.join("; "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks a deck against a format's legality, deck size and copy limit rules.
+/// `cards` holds the database entry for each deck entry in the same order,
+/// with `None` for entries that couldn't be found, and `candidates` the cards
+/// each entry could be when its name is ambiguous
+pub(crate) fn check_deck(
+    deck: &Deck,
+    cards: &[Option<DbCard>],
+    candidates: &[Vec<String>],
+    format: Format,
+) -> Vec<Violation> {
+    let rules = format.rules();
+    let mut violations = Vec::new();
+
+    let deck_size: u32 = deck
+        .cards
+        .iter()
+        .filter(|entry| entry.section != DeckSection::Sideboard)
+        .map(|entry| entry.quantity)
+        .sum();
+
+    if deck_size < rules.min_deck_size {
+        violations.push(Violation::new(
+            format!(
+                "Deck has {deck_size} cards, {format} needs at least {}",
+                rules.min_deck_size
+            ),
+            &[],
+        ));
+    }
+
+    if let Some(max_deck_size) = rules.max_deck_size.filter(|max| deck_size > *max) {
+        violations.push(Violation::new(
+            format!("Deck has {deck_size} cards, {format} allows at most {max_deck_size}"),
+            &[],
+        ));
+    }
+
+    // A companion sits outside a Commander deck rather than in a sideboard
+    let sideboard: Vec<&DeckEntry> = deck
+        .cards
+        .iter()
+        .zip(cards)
+        .filter(|(entry, _)| entry.section == DeckSection::Sideboard)
+        .filter(|(_, card)| !format.has_commander() || !card.as_ref().is_some_and(is_companion))
+        .map(|(entry, _)| entry)
+        .collect();
+
+    let sideboard_size: u32 = sideboard.iter().map(|entry| entry.quantity).sum();
+    if sideboard_size > rules.max_sideboard {
+        violations.push(Violation::new(
+            format!(
+                "Sideboard has {sideboard_size} cards, {format} allows at most {}",
+                rules.max_sideboard
+            ),
+            &sideboard,
+        ));
+    }
+
+    if format.has_commander() {
+        violations.extend(check_commander(deck, cards, format));
+    }
+
+    // Group entries by card so legality and copies are checked across the
+    // main deck, sideboard and command zone together
+    let mut grouped: Vec<(String, &DbCard, Vec<&DeckEntry>)> = Vec::new();
+    for ((entry, card), candidates) in deck.cards.iter().zip(cards).zip(candidates) {
+        let Some(card) = card else {
+            let message = if candidates.is_empty() {
+                "Unknown card".to_string()
+            } else {
+                format!(
+                    "Ambiguous card, matching: {}",
+                    format_candidates(candidates)
+                )
+            };
+
+            violations.push(Violation::new(message, &[entry]));
+            continue;
+        };
+
+        let name = card.name.clone().unwrap_or_else(|| entry.name.clone());
+        let key = normalise_name(&name);
+        match grouped.iter_mut().find(|(k, _, _)| *k == key) {
+            Some((_, _, entries)) => entries.push(entry),
+            None => grouped.push((key, card, vec![entry])),
+        }
+    }
+
+    for (_, card, entries) in grouped {
+        let name = card.name.as_deref().unwrap_or_default();
+        let legality = Legality::of(card, format);
+        match legality {
+            Legality::Banned => {
+                violations.push(Violation::new(format!("'{name}' is banned"), &entries));
+                continue;
+            }
+            Legality::NotLegal => {
+                violations.push(Violation::new(
+                    format!("'{name}' is not legal in {format}"),
+                    &entries,
+                ));
+                continue;
+            }
+            Legality::Legal | Legality::Restricted => {}
+        }
+
+        let copies: u32 = entries.iter().map(|entry| entry.quantity).sum();
+        let limit = if legality == Legality::Restricted {
+            1
+        } else {
+            copy_limit(card, rules.copy_limit)
+        };
+
+        if copies > limit {
+            let reason = if legality == Legality::Restricted {
+                " (restricted)"
+            } else {
+                ""
+            };
+
+            violations.push(Violation::new(
+                format!("{copies} copies of '{name}', max {limit}{reason}"),
+                &entries,
+            ));
+        }
+    }
+
+    violations
+}
+
+/// Number of copies of a card allowed in a deck, accounting for basic lands and
+/// cards like Relentless Rats or Seven Dwarves that override the usual limit
+pub(crate) fn copy_limit(card: &DbCard, default: u32) -> u32 {
+    if card.type_line.as_deref().is_some_and(is_basic_land) {
+        return u32::MAX;
+    }
+
+    let Some(oracle_text) = card.oracle_text.as_deref() else {
+        return default;
+    };
+
+    let oracle_text = oracle_text.to_lowercase();
+    if oracle_text.contains("a deck can have any number of cards named") {
+        return u32::MAX;
+    }
+
+    let Some((_, rest)) = oracle_text.split_once("a deck can have up to ") else {
+        return default;
+    };
+
+    let limit = rest
+        .split_whitespace()
+        .next()
+        .and_then(|count| match count {
+            "one" => Some(1),
+            "two" => Some(2),
+            "three" => Some(3),
+            "four" => Some(4),
+            "five" => Some(5),
+            "six" => Some(6),
+            "seven" => Some(7),
+            "eight" => Some(8),
+            "nine" => Some(9),
+            "ten" => Some(10),
+            count => count.parse().ok(),
+        });
+
+    limit.unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legalities(legality: &str) -> String {
+        let legalities: HashMap<&str, &str> = Format::value_variants()
+            .iter()
+            .map(|format| (format.key(), legality))
+            .collect();
+
+        serde_json::to_string(&legalities).unwrap()
+    }
+
+    fn card(name: &str, type_line: &str, oracle_text: &str) -> DbCard {
+        DbCard {
+            name: Some(name.to_string()),
+            type_line: Some(type_line.to_string()),
+            oracle_text: Some(oracle_text.to_string()),
+            legalities: Some(legalities("legal")),
+            ..Default::default()
+        }
+    }
+
+    fn bolt() -> DbCard {
+        card(
+            "Lightning Bolt",
+            "Instant",
+            "Lightning Bolt deals 3 damage to any target.",
+        )
+    }
+
+    fn mountain() -> DbCard {
+        card("Mountain", "Basic Land — Mountain", "({T}: Add {R}.)")
+    }
+
+    fn krenko() -> DbCard {
+        DbCard {
+            color_identity: Some("R".to_string()),
+            ..card(
+                "Krenko, Mob Boss",
+                "Legendary Creature — Goblin Warrior",
+                "{T}: Create X 1/1 red Goblin creature tokens, where X is the number of Goblins you control.",
+            )
+        }
+    }
+
+    fn check(cards: Vec<(u32, DeckSection, DbCard)>, format: Format) -> Vec<String> {
+        let entries = cards
+            .iter()
+            .map(|(quantity, section, card)| {
+                DeckEntry::new(*quantity, card.name.clone().unwrap(), *section)
+            })
+            .collect();
+        let deck = Deck::new(entries);
+        let cards: Vec<Option<DbCard>> = cards.into_iter().map(|(_, _, card)| Some(card)).collect();
+        let candidates = vec![Vec::new(); cards.len()];
+
+        check_deck(&deck, &cards, &candidates, format)
+            .into_iter()
+            .map(|violation| violation.message)
+            .collect()
+    }
+
+    /// A 60 card Modern deck of four Lightning Bolts and Mountains
+    fn burn() -> Vec<(u32, DeckSection, DbCard)> {
+        vec![
+            (4, DeckSection::Main, bolt()),
+            (56, DeckSection::Main, mountain()),
+        ]
+    }
+
+    /// A 100 card Commander deck led by Krenko
+    fn goblins() -> Vec<(u32, DeckSection, DbCard)> {
+        vec![
+            (1, DeckSection::Commander, krenko()),
+            (99, DeckSection::Main, mountain()),
+        ]
+    }
+
+    #[test]
+    fn limits_copies_by_oracle_text() {
+        let rats = card(
+            "Relentless Rats",
+            "Creature — Rat",
+            "Relentless Rats gets +1/+1 for each other creature you control named Relentless Rats.\nA deck can have any number of cards named Relentless Rats.",
+        );
+        let dwarves = card(
+            "Seven Dwarves",
+            "Creature — Dwarf",
+            "Seven Dwarves gets +1/+1 for each other creature named Seven Dwarves you control.\nA deck can have up to seven cards named Seven Dwarves.",
+        );
+        let snow_forest = card(
+            "Snow-Covered Forest",
+            "Basic Snow Land — Forest",
+            "({T}: Add {G}.)",
+        );
+        let wastes = card("Wastes", "Basic Land", "{T}: Add {C}.");
+
+        assert_eq!(copy_limit(&bolt(), 4), 4);
+        assert_eq!(copy_limit(&bolt(), 1), 1);
+        assert_eq!(copy_limit(&rats, 4), u32::MAX);
+        assert_eq!(copy_limit(&dwarves, 4), 7);
+        assert_eq!(copy_limit(&dwarves, 1), 7);
+        assert_eq!(copy_limit(&mountain(), 1), u32::MAX);
+        assert_eq!(copy_limit(&snow_forest, 4), u32::MAX);
+        assert_eq!(copy_limit(&wastes, 1), u32::MAX);
+    }
+
+    #[test]
+    fn accepts_legal_deck() {
+        assert!(check(burn(), Format::Modern).is_empty());
+        assert!(check(goblins(), Format::Commander).is_empty());
+    }
+
+    #[test]
+    fn counts_copies_across_sections() {
+        let mut deck = burn();
+        deck.push((1, DeckSection::Sideboard, bolt()));
+
+        assert_eq!(
+            check(deck, Format::Modern),
+            ["5 copies of 'Lightning Bolt', max 4"]
+        );
+    }
+
+    #[test]
+    fn limits_restricted_cards_to_one() {
+        let recall = || DbCard {
+            legalities: Some(legalities("restricted")),
+            ..card(
+                "Ancestral Recall",
+                "Instant",
+                "Target player draws three cards.",
+            )
+        };
+
+        let mut deck = burn();
+        deck[1].0 = 55;
+        deck.push((1, DeckSection::Main, recall()));
+        assert!(check(deck.clone(), Format::Vintage).is_empty());
+
+        deck.push((1, DeckSection::Sideboard, recall()));
+        assert_eq!(
+            check(deck, Format::Vintage),
+            ["2 copies of 'Ancestral Recall', max 1 (restricted)"]
+        );
+    }
+
+    #[test]
+    fn reports_banned_and_not_legal_cards() {
+        let banned = DbCard {
+            legalities: Some(legalities("banned")),
+            ..card(
+                "Black Lotus",
+                "Artifact",
+                "{T}, Sacrifice Black Lotus: Add three mana of any one color.",
+            )
+        };
+        let not_legal = DbCard {
+            legalities: None,
+            ..card("Shahrazad", "Sorcery", "Players play a Magic subgame.")
+        };
+
+        let mut deck = burn();
+        deck.push((1, DeckSection::Sideboard, banned));
+        deck.push((1, DeckSection::Sideboard, not_legal));
+        assert_eq!(
+            check(deck, Format::Modern),
+            [
+                "'Black Lotus' is banned",
+                "'Shahrazad' is not legal in Modern"
+            ]
+        );
+    }
+
+    #[test]
+    fn allows_any_number_of_basic_lands() {
+        let snow_mountain = card(
+            "Snow-Covered Mountain",
+            "Basic Snow Land — Mountain",
+            "({T}: Add {R}.)",
+        );
+
+        let mut deck = goblins();
+        deck[1].0 = 50;
+        deck.push((49, DeckSection::Main, snow_mountain));
+        assert!(check(deck, Format::Commander).is_empty());
+    }
+
+    #[test]
+    fn checks_deck_size_per_format() {
+        let mut deck = burn();
+        deck[1].0 = 55;
+        assert_eq!(
+            check(deck, Format::Modern),
+            ["Deck has 59 cards, Modern needs at least 60"]
+        );
+
+        let mut deck = burn();
+        deck[1].0 = 200;
+        assert!(check(deck, Format::Legacy).is_empty());
+
+        let mut deck = goblins();
+        deck[1].0 = 100;
+        assert_eq!(
+            check(deck, Format::Commander),
+            ["Deck has 101 cards, Commander allows at most 100"]
+        );
+
+        let mut deck = goblins();
+        deck[1].0 = 98;
+        assert_eq!(
+            check(deck, Format::Commander),
+            ["Deck has 99 cards, Commander needs at least 100"]
+        );
+
+        let mut deck = goblins();
+        deck[0].2.type_line = Some("Legendary Planeswalker — Chandra".to_string());
+        deck[1].0 = 58;
+        deck.push((1, DeckSection::Commander, bolt()));
+        assert!(check(deck.clone(), Format::Oathbreaker).is_empty());
+
+        deck[1].0 = 99;
+        assert_eq!(
+            check(deck, Format::Oathbreaker),
+            ["Deck has 101 cards, Oathbreaker allows at most 60"]
+        );
+    }
+
+    #[test]
+    fn limits_sideboard_to_fifteen_cards() {
+        let mut deck = burn();
+        deck.push((15, DeckSection::Sideboard, mountain()));
+        assert!(check(deck.clone(), Format::Modern).is_empty());
+
+        deck.push((1, DeckSection::Sideboard, mountain()));
+        assert_eq!(
+            check(deck, Format::Modern),
+            ["Sideboard has 16 cards, Modern allows at most 15"]
+        );
+    }
+
+    #[test]
+    fn keeps_companion_out_of_commander_sideboard() {
+        let lurrus = card(
+            "Lurrus of the Dream-Den",
+            "Legendary Creature — Cat Nightmare",
+            "Companion — Each permanent card in your starting deck has mana value 2 or less.\nLifelink",
+        );
+
+        let mut deck = goblins();
+        deck.push((1, DeckSection::Sideboard, lurrus));
+        assert!(check(deck.clone(), Format::Commander).is_empty());
+
+        deck.push((1, DeckSection::Sideboard, bolt()));
+        assert_eq!(
+            check(deck, Format::Commander),
+            ["Sideboard has 1 cards, Commander allows at most 0"]
+        );
+    }
+}
@@ -3,7 +3,21 @@ use clap::Parser;
 
 pub(crate) mod card;
 pub(crate) mod cli;
+pub(crate) mod collection;
+pub(crate) mod commander;
+pub(crate) mod diff;
+pub(crate) mod formats;
+pub(crate) mod fuzzy;
+pub(crate) mod ledger;
+pub(crate) mod legality;
 pub(crate) mod loader;
+pub(crate) mod manabase;
+pub(crate) mod plan;
+pub(crate) mod query;
+pub(crate) mod saved;
+pub(crate) mod scanner;
+pub(crate) mod simulate;
+pub(crate) mod stats;
 pub(crate) mod store;
 pub(crate) mod utils;
 
@@ -15,15 +29,83 @@ async fn main() -> Result<()> {
 
     match cli.command {
         Commands::Init => commands::init().await?,
-        Commands::Sync => commands::sync().await?,
+        Commands::Sync {
+            threshold,
+            currency,
+        } => commands::sync(threshold, currency).await?,
         Commands::Clean => commands::clean().await?,
         Commands::Get { card } => commands::get(card).await?,
+        Commands::Search {
+            query,
+            card_type,
+            currency,
+            limit,
+            sort,
+            desc,
+        } => commands::search(query, card_type, currency, limit, sort, desc).await?,
         Commands::Price {
             card,
             deck,
+            deck_format,
+            options,
+        } => commands::price(card, deck, deck_format, options).await?,
+        Commands::Convert {
+            deck,
+            to,
+            deck_format,
+            output,
+        } => commands::convert(deck, to, deck_format, output).await?,
+        Commands::Check {
+            deck,
+            format,
+            deck_format,
+        } => commands::check(deck, format, deck_format).await?,
+        Commands::Formats {
+            deck,
+            all,
+            deck_format,
+        } => commands::formats(deck, all, deck_format).await?,
+        Commands::Stats {
+            deck,
+            json,
+            deck_format,
+        } => commands::stats(deck, json, deck_format).await?,
+        Commands::Mana {
+            deck,
+            json,
+            deck_format,
+        } => commands::mana(deck, json, deck_format).await?,
+        Commands::Diff {
+            old,
+            new,
             currency,
-            exact_match,
-        } => commands::price(card, deck, currency, exact_match).await?,
+            include_basics,
+            deck_format,
+        } => commands::diff(old, new, currency, include_basics, deck_format).await?,
+        Commands::Simulate {
+            command:
+                Some(SimulateCommands::Hypergeometric {
+                    deck_size,
+                    copies,
+                    draws,
+                    hits,
+                }),
+            ..
+        } => commands::hypergeometric(deck_size, copies, draws, hits)?,
+        Commands::Collection { command } => commands::collection(command).await?,
+        Commands::Plan {
+            decks,
+            currency,
+            include_basics,
+            deck_format,
+        } => commands::plan(decks, currency, include_basics, deck_format).await?,
+        Commands::Deck { command } => commands::deck(command).await?,
+        Commands::Ledger { command } => commands::ledger(command).await?,
+        Commands::Simulate {
+            command: None,
+            deck,
+            options,
+        } => commands::simulate(deck.unwrap_or_default(), options).await?,
     }
 
     Ok(())
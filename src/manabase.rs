@@ -0,0 +1,438 @@
+use serde::Serialize;
+
+use crate::card::{DbCard, Deck, DeckSection, COLOURS};
+
+const BASIC_LAND_TYPES: [(&str, char); 5] = [
+    ("plains", 'W'),
+    ("island", 'U'),
+    ("swamp", 'B'),
+    ("mountain", 'R'),
+    ("forest", 'G'),
+];
+
+/// Nonland permanents up to this mana value with a mana ability (mana rocks and dorks)
+/// count as sources
+const MAX_SOURCE_CMC: f32 = 2.0;
+
+/// Coloured sources needed to cast a spell on curve around 90% of the time, based on
+/// Frank Karsten's tables. Rows are coloured pips (1 to 3+), columns are the generic
+/// mana in the cost (0 to 5+)
+const SOURCES_40: [[u32; 6]; 3] = [
+    [9, 9, 8, 7, 6, 6],
+    [14, 12, 11, 10, 9, 9],
+    [16, 15, 13, 12, 12, 12],
+];
+const SOURCES_60: [[u32; 6]; 3] = [
+    [14, 13, 12, 11, 10, 9],
+    [20, 18, 16, 15, 14, 14],
+    [23, 21, 19, 18, 18, 18],
+];
+const SOURCES_99: [[u32; 6]; 3] = [
+    [19, 19, 18, 16, 15, 14],
+    [30, 28, 26, 23, 22, 22],
+    [36, 33, 30, 28, 28, 28],
+];
+
+/// Sources and requirements for a single colour
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct ColourSources {
+    pub(crate) colour: char,
+    pub(crate) lands: u32,
+    pub(crate) other: u32,
+    pub(crate) needed: u32,
+    /// Card with the hardest requirement for this colour and its mana cost
+    pub(crate) hardest: Option<(String, String)>,
+}
+
+impl ColourSources {
+    pub(crate) fn sources(&self) -> u32 {
+        self.lands + self.other
+    }
+
+    pub(crate) fn is_short(&self) -> bool {
+        self.sources() < self.needed
+    }
+}
+
+/// Coloured mana sources of a deck compared against what its spells need
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct ManaBase {
+    pub(crate) cards: u32,
+    pub(crate) lands: u32,
+    pub(crate) colours: Vec<ColourSources>,
+    /// Deck entries that couldn't be found, which aren't included in the analysis
+    pub(crate) unknown: Vec<String>,
+}
+
+impl ManaBase {
+    /// Analyses the main deck and command zone, with `cards` holding the database
+    /// entry for each deck entry in the same order
+    pub(crate) fn new(deck: &Deck, cards: &[Option<DbCard>]) -> Self {
+        let mut mana_base = Self::default();
+        let entries: Vec<(u32, &DbCard)> = deck
+            .cards
+            .iter()
+            .zip(cards)
+            .filter(|(entry, _)| entry.section != DeckSection::Sideboard)
+            .filter_map(|(entry, card)| {
+                mana_base.cards += entry.quantity;
+                if card.is_none() {
+                    mana_base.unknown.push(entry.name.clone());
+                }
+
+                Some((entry.quantity, card.as_ref()?))
+            })
+            .collect();
+
+        // Fetch lands that find any basic can only get the basics in the deck
+        let basic_colours: String = entries
+            .iter()
+            .filter(|(_, card)| card.has_type("Basic"))
+            .flat_map(|(_, card)| card.produced_mana.as_deref().unwrap_or_default().chars())
+            .collect();
+
+        let table = match mana_base.cards {
+            0..=45 => &SOURCES_40,
+            46..=80 => &SOURCES_60,
+            _ => &SOURCES_99,
+        };
+
+        let mut colours: Vec<ColourSources> = COLOURS
+            .iter()
+            .map(|colour| ColourSources {
+                colour: *colour,
+                lands: 0,
+                other: 0,
+                needed: 0,
+                hardest: None,
+            })
+            .collect();
+
+        for (quantity, card) in entries {
+            let is_land = card.has_type("Land");
+            if is_land {
+                mana_base.lands += quantity;
+            }
+
+            let is_source = is_land
+                || (!card.has_type("Instant")
+                    && !card.has_type("Sorcery")
+                    && card.cmc.unwrap_or_default() <= MAX_SOURCE_CMC
+                    && has_mana_ability(card));
+            if is_source {
+                let produced = produced_colours(card, &basic_colours);
+                for colour in colours.iter_mut() {
+                    if !produced.contains(colour.colour) {
+                        continue;
+                    }
+
+                    if is_land {
+                        colour.lands += quantity;
+                    } else {
+                        colour.other += quantity;
+                    }
+                }
+            }
+
+            if is_land {
+                continue;
+            }
+
+            let mana_cost = card.mana_cost.as_deref().unwrap_or_default();
+            for face_cost in mana_cost.split(" // ") {
+                let (pips, total) = count_cost(face_cost);
+                for (colour, pips) in colours.iter_mut().zip(pips) {
+                    if pips == 0 {
+                        continue;
+                    }
+
+                    // Other colours in the cost count as generic mana for this colour
+                    let generic = total - pips;
+                    let needed = table[pips.min(3) as usize - 1][generic.min(5) as usize];
+                    if needed > colour.needed {
+                        colour.needed = needed;
+                        colour.hardest =
+                            Some((card.name.clone().unwrap_or_default(), face_cost.to_string()));
+                    }
+                }
+            }
+        }
+
+        mana_base.colours = colours
+            .into_iter()
+            .filter(|colour| colour.needed > 0 || colour.sources() > 0)
+            .collect();
+
+        mana_base
+    }
+
+    pub(crate) fn short_colours(&self) -> impl Iterator<Item = &ColourSources> {
+        self.colours.iter().filter(|colour| colour.is_short())
+    }
+}
+
+impl std::fmt::Display for ManaBase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "[*] Lands: {} of {} cards", self.lands, self.cards)?;
+        for colour in self.colours.iter() {
+            write!(
+                f,
+                "[*] {}: {} sources ({} lands, {} other)",
+                colour.colour,
+                colour.sources(),
+                colour.lands,
+                colour.other
+            )?;
+
+            if let Some((name, cost)) = &colour.hardest {
+                write!(f, ", {} needed for {name} {cost}", colour.needed)?;
+            }
+
+            if colour.is_short() {
+                write!(f, " - {} short", colour.needed - colour.sources())?;
+            }
+            writeln!(f)?;
+        }
+
+        let short: Vec<String> = self
+            .short_colours()
+            .map(|colour| format!("{} ({}/{})", colour.colour, colour.sources(), colour.needed))
+            .collect();
+
+        if short.is_empty() {
+            writeln!(f, "\n[*] Every colour has enough sources")?;
+        } else {
+            writeln!(
+                f,
+                "\n[*] Colours below the recommended sources: {}",
+                short.join(", ")
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether a card can tap for mana again and again, e.g. `{T}: Add {G}.` or
+/// `{1}, {T}: Add {W}{U}.`, rather than making one-off mana such as Treasure tokens
+fn has_mana_ability(card: &DbCard) -> bool {
+    let oracle_text = card
+        .oracle_text
+        .as_deref()
+        .unwrap_or_default()
+        .to_lowercase();
+
+    oracle_text
+        .lines()
+        .any(|line| match line.split_once(": add ") {
+            Some((cost, _)) => cost.contains("{t}") && !cost.contains("sacrifice"),
+            None => false,
+        })
+}
+
+/// Colours of mana a card can make, including the colours fetch lands can find
+fn produced_colours(card: &DbCard, basic_colours: &str) -> String {
+    let mut produced = card.produced_mana.clone().unwrap_or_default();
+    if !card.has_type("Land") {
+        return produced;
+    }
+
+    let oracle_text = card
+        .oracle_text
+        .as_deref()
+        .unwrap_or_default()
+        .to_lowercase();
+
+    for search in oracle_text.split("search your library for ").skip(1) {
+        let search = search.split(" card").next().unwrap_or_default();
+        let mut found_type = false;
+        for (land_type, colour) in BASIC_LAND_TYPES {
+            if search.contains(land_type) {
+                produced.push(colour);
+                found_type = true;
+            }
+        }
+
+        if !found_type && search.contains("basic land") {
+            produced.push_str(basic_colours);
+        }
+    }
+
+    produced
+}
+
+/// Counts the strictly coloured pips of each colour in a mana cost (hybrid and
+/// phyrexian symbols can be paid other ways) along with its total mana value
+fn count_cost(mana_cost: &str) -> ([u32; COLOURS.len()], u32) {
+    let mut pips = [0; COLOURS.len()];
+    let mut other = 0;
+    for symbol in mana_cost.split(['{', '}']).filter(|s| !s.is_empty()) {
+        if let Ok(generic) = symbol.parse::<u32>() {
+            other += generic;
+        } else if let Some(colour) = COLOURS.iter().position(|c| symbol == c.to_string()) {
+            pips[colour] += 1;
+        } else if symbol != "X" {
+            other += 1;
+        }
+    }
+
+    let total = pips.iter().sum::<u32>() + other;
+    (pips, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::DeckEntry;
+
+    fn card(oracle_text: &str) -> DbCard {
+        DbCard {
+            oracle_text: Some(oracle_text.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn only_counts_repeatable_mana_abilities() {
+        assert!(has_mana_ability(&card("{T}: Add {G}.")));
+        assert!(has_mana_ability(&card(
+            "Flying\n{T}: Add one mana of any color."
+        )));
+        assert!(has_mana_ability(&card("{1}, {T}: Add {W}{U}.")));
+
+        assert!(!has_mana_ability(&card(
+            "When this creature enters, create a Treasure token."
+        )));
+        assert!(!has_mana_ability(&card(
+            "{T}, Sacrifice this artifact: Add one mana of any color."
+        )));
+        assert!(!has_mana_ability(&card("{T}: Draw a card.")));
+    }
+
+    #[test]
+    fn counts_strict_pips_and_mana_value() {
+        assert_eq!(count_cost("{2}{U}{U}"), ([0, 2, 0, 0, 0], 4));
+        assert_eq!(count_cost("{X}{R}"), ([0, 0, 0, 1, 0], 1));
+        assert_eq!(count_cost("{W/U}{B/P}{C}"), ([0, 0, 0, 0, 0], 3));
+        assert_eq!(count_cost(""), ([0, 0, 0, 0, 0], 0));
+    }
+
+    fn deck_card(
+        type_line: &str,
+        mana_cost: &str,
+        cmc: f32,
+        oracle_text: &str,
+        produced_mana: &str,
+    ) -> DbCard {
+        DbCard {
+            type_line: Some(type_line.to_string()),
+            mana_cost: Some(mana_cost.to_string()),
+            cmc: Some(cmc),
+            produced_mana: Some(produced_mana.to_string()),
+            ..card(oracle_text)
+        }
+    }
+
+    fn mana_base(cards: Vec<(u32, DbCard)>) -> ManaBase {
+        let deck = Deck::new(
+            cards
+                .iter()
+                .map(|(quantity, _)| DeckEntry::new(*quantity, "Card", DeckSection::Main))
+                .collect(),
+        );
+        let cards: Vec<Option<DbCard>> = cards.into_iter().map(|(_, card)| Some(card)).collect();
+        ManaBase::new(&deck, &cards)
+    }
+
+    fn bolt() -> DbCard {
+        deck_card(
+            "Instant",
+            "{R}",
+            1.0,
+            "Lightning Bolt deals 3 damage to any target.",
+            "",
+        )
+    }
+
+    fn filler() -> DbCard {
+        deck_card("Artifact Creature — Thopter", "{0}", 0.0, "Flying", "")
+    }
+
+    #[test]
+    fn compares_sources_against_karsten_tables() {
+        let mana_base = mana_base(vec![
+            (4, bolt()),
+            (4, deck_card("Instant", "{U}{U}", 2.0, "Counter target spell.", "")),
+            (4, deck_card("Creature — Elf Druid", "{G}", 1.0, "{T}: Add {G}.", "G")),
+            (
+                4,
+                deck_card(
+                    "Land",
+                    "",
+                    0.0,
+                    "{T}, Pay 1 life, Sacrifice Wooded Foothills: Search your library for a Mountain or Forest card, put it onto the battlefield, then shuffle.",
+                    "",
+                ),
+            ),
+            (10, deck_card("Basic Land — Mountain", "", 0.0, "({T}: Add {R}.)", "R")),
+            (10, deck_card("Basic Land — Island", "", 0.0, "({T}: Add {U}.)", "U")),
+            (24, filler()),
+        ]);
+
+        assert_eq!(mana_base.cards, 60);
+        assert_eq!(mana_base.lands, 24);
+
+        let sources: Vec<(char, u32, u32, u32)> = mana_base
+            .colours
+            .iter()
+            .map(|colour| (colour.colour, colour.lands, colour.other, colour.needed))
+            .collect();
+        assert_eq!(
+            sources,
+            [('U', 10, 0, 20), ('R', 14, 0, 14), ('G', 4, 4, 14)]
+        );
+        let hardest = mana_base.colours[0].hardest.as_ref();
+        assert_eq!(hardest.map(|(_, cost)| cost.as_str()), Some("{U}{U}"));
+
+        let short: Vec<char> = mana_base
+            .short_colours()
+            .map(|colour| colour.colour)
+            .collect();
+        assert_eq!(short, ['U', 'G']);
+    }
+
+    #[test]
+    fn picks_table_by_deck_size() {
+        let needed = |cards: u32| {
+            let mana_base = mana_base(vec![(1, bolt()), (cards - 1, filler())]);
+            mana_base.colours[0].needed
+        };
+
+        assert_eq!(needed(40), 9);
+        assert_eq!(needed(60), 14);
+        assert_eq!(needed(100), 19);
+    }
+
+    #[test]
+    fn counts_other_colours_as_generic_mana() {
+        let mana_base = mana_base(vec![
+            (
+                1,
+                deck_card("Instant", "{1}{U}{U}{U}", 4.0, "Choose two.", ""),
+            ),
+            (
+                1,
+                deck_card("Sorcery", "{1}{U}{R}", 3.0, "Draw two cards.", ""),
+            ),
+            (58, filler()),
+        ]);
+
+        // Three blue pips with one generic, and one red pip with two other mana
+        let needed: Vec<(char, u32)> = mana_base
+            .colours
+            .iter()
+            .map(|colour| (colour.colour, colour.needed))
+            .collect();
+        assert_eq!(needed, [('U', 21), ('R', 12)]);
+    }
+}
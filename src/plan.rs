@@ -0,0 +1,240 @@
+use crate::utils::normalise_name;
+
+/// Most decks that can be planned together, as every combination is checked
+pub(crate) const MAX_DECKS: usize = 16;
+
+/// Copies of a card owned and needed by each deck being planned
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CardNeeds {
+    pub(crate) name: String,
+    pub(crate) owned: u32,
+    /// Copies needed by each deck, in the order the decks were given
+    pub(crate) needed: Vec<u32>,
+}
+
+impl CardNeeds {
+    pub(crate) fn total(&self) -> u32 {
+        self.needed.iter().sum()
+    }
+
+    /// Copies to buy to build every deck at the same time
+    pub(crate) fn shortfall(&self) -> u32 {
+        self.total().saturating_sub(self.owned)
+    }
+
+    /// Owned copies needed by more than one deck, with too few to go round
+    pub(crate) fn is_conflict(&self) -> bool {
+        let decks = self.needed.iter().filter(|needed| **needed > 0).count();
+        self.owned > 0 && decks > 1 && self.total() > self.owned
+    }
+}
+
+/// Cards needed to build several decks from one collection
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct BuildPlan {
+    pub(crate) decks: Vec<String>,
+    pub(crate) cards: Vec<CardNeeds>,
+}
+
+impl BuildPlan {
+    /// Plans building `decks`, each given as a name and the copies of each card
+    /// it needs, from the `owned` copies of each card keyed by normalised name
+    pub(crate) fn new(decks: Vec<(String, Vec<(String, u32)>)>, owned: &[(String, u32)]) -> Self {
+        let mut plan = Self::default();
+        let deck_count = decks.len();
+        let mut keys: Vec<String> = Vec::new();
+        for (index, (deck, cards)) in decks.into_iter().enumerate() {
+            plan.decks.push(deck);
+            for (name, quantity) in cards {
+                let key = normalise_name(&name);
+                let position = match keys.iter().position(|k| *k == key) {
+                    Some(position) => position,
+                    None => {
+                        let owned = owned
+                            .iter()
+                            .find(|(k, _)| *k == key)
+                            .map(|(_, quantity)| *quantity)
+                            .unwrap_or_default();
+
+                        keys.push(key);
+                        plan.cards.push(CardNeeds {
+                            name,
+                            owned,
+                            needed: vec![0; deck_count],
+                        });
+                        plan.cards.len() - 1
+                    }
+                };
+
+                plan.cards[position].needed[index] += quantity;
+            }
+        }
+
+        plan
+    }
+
+    /// Copies of cards a deck is missing when built on its own
+    pub(crate) fn missing(&self, deck: usize) -> u32 {
+        self.cards
+            .iter()
+            .map(|card| card.needed[deck].saturating_sub(card.owned))
+            .sum()
+    }
+
+    /// Whether the collection has enough copies to build all of `decks` at once
+    pub(crate) fn can_build(&self, decks: &[usize]) -> bool {
+        self.cards.iter().all(|card| {
+            let needed: u32 = decks.iter().map(|deck| card.needed[*deck]).sum();
+            needed <= card.owned
+        })
+    }
+
+    /// The largest groups of decks that can be built at the same time, empty
+    /// when no deck can be built at all
+    pub(crate) fn largest_buildable(&self) -> Vec<Vec<usize>> {
+        let deck_count = self.decks.len();
+        let mut groups: Vec<Vec<usize>> = (1..1_usize << deck_count)
+            .map(|mask| {
+                (0..deck_count)
+                    .filter(|deck| mask & (1 << deck) != 0)
+                    .collect::<Vec<usize>>()
+            })
+            .filter(|group| self.can_build(group))
+            .collect();
+
+        let largest = groups
+            .iter()
+            .map(|group| group.len())
+            .max()
+            .unwrap_or_default();
+        groups.retain(|group| group.len() == largest);
+        groups.sort();
+        groups
+    }
+
+    pub(crate) fn conflicts(&self) -> impl Iterator<Item = &CardNeeds> {
+        self.cards.iter().filter(|card| card.is_conflict())
+    }
+
+    /// Cards to buy to build every deck at the same time
+    pub(crate) fn shopping_list(&self) -> impl Iterator<Item = &CardNeeds> {
+        self.cards.iter().filter(|card| card.shortfall() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(decks: &[(&str, &[(&str, u32)])], owned: &[(&str, u32)]) -> BuildPlan {
+        let decks = decks
+            .iter()
+            .map(|(deck, cards)| {
+                let cards = cards
+                    .iter()
+                    .map(|(name, quantity)| (name.to_string(), *quantity))
+                    .collect();
+                (deck.to_string(), cards)
+            })
+            .collect();
+        let owned: Vec<(String, u32)> = owned
+            .iter()
+            .map(|(name, quantity)| (normalise_name(name), *quantity))
+            .collect();
+
+        BuildPlan::new(decks, &owned)
+    }
+
+    fn names<'a>(cards: impl Iterator<Item = &'a CardNeeds>) -> Vec<&'a str> {
+        cards.map(|card| card.name.as_str()).collect()
+    }
+
+    #[test]
+    fn merges_cards_across_decks() {
+        let plan = plan(
+            &[
+                ("burn", &[("Lightning Bolt", 4), ("Mountain", 16)]),
+                ("jund", &[("lightning bolt", 2), ("Tarmogoyf", 4)]),
+            ],
+            &[("Lightning Bolt", 4), ("Tarmogoyf", 1)],
+        );
+
+        assert_eq!(plan.decks, ["burn", "jund"]);
+        assert_eq!(
+            plan.cards[0],
+            CardNeeds {
+                name: "Lightning Bolt".to_string(),
+                owned: 4,
+                needed: vec![4, 2],
+            }
+        );
+        assert_eq!(plan.cards[1].needed, [16, 0]);
+        assert_eq!(plan.cards[2].needed, [0, 4]);
+        assert_eq!(plan.missing(0), 16);
+        assert_eq!(plan.missing(1), 3);
+    }
+
+    #[test]
+    fn finds_the_largest_buildable_groups() {
+        let plan = plan(
+            &[
+                ("burn", &[("Lightning Bolt", 4)]),
+                ("jund", &[("Lightning Bolt", 2), ("Tarmogoyf", 2)]),
+                ("storm", &[("Lightning Bolt", 2)]),
+                ("tron", &[("Karn Liberated", 4)]),
+            ],
+            &[("Lightning Bolt", 4), ("Tarmogoyf", 2)],
+        );
+
+        assert!(plan.can_build(&[0]));
+        assert!(!plan.can_build(&[0, 1]));
+        assert!(!plan.can_build(&[3]));
+        assert_eq!(plan.largest_buildable(), [vec![1, 2]]);
+    }
+
+    #[test]
+    fn finds_nothing_buildable_without_cards() {
+        let plan = plan(&[("burn", &[("Lightning Bolt", 4)])], &[]);
+
+        assert!(plan.largest_buildable().is_empty());
+    }
+
+    #[test]
+    fn checks_every_combination_of_the_most_decks() {
+        let decks: Vec<(String, &[(&str, u32)])> = (0..MAX_DECKS)
+            .map(|deck| (format!("deck {deck}"), &[("Sol Ring", 1)][..]))
+            .collect();
+        let decks: Vec<(&str, &[(&str, u32)])> = decks
+            .iter()
+            .map(|(deck, cards)| (deck.as_str(), *cards))
+            .collect();
+        let plan = plan(&decks, &[("Sol Ring", 2)]);
+
+        let groups = plan.largest_buildable();
+        assert_eq!(groups.len(), MAX_DECKS * (MAX_DECKS - 1) / 2);
+        assert_eq!(groups[0], [0, 1]);
+        assert_eq!(groups[groups.len() - 1], [MAX_DECKS - 2, MAX_DECKS - 1]);
+    }
+
+    #[test]
+    fn lists_conflicts_and_cards_to_buy() {
+        let plan = plan(
+            &[
+                ("burn", &[("Lightning Bolt", 4), ("Goblin Guide", 4)]),
+                ("jund", &[("Lightning Bolt", 2), ("Tarmogoyf", 4)]),
+                ("mono red", &[("Goblin Guide", 2)]),
+            ],
+            &[("Lightning Bolt", 4), ("Tarmogoyf", 4)],
+        );
+
+        // Goblin Guide is short but none are owned, so there's nothing to share
+        assert_eq!(names(plan.conflicts()), ["Lightning Bolt"]);
+        assert_eq!(
+            names(plan.shopping_list()),
+            ["Lightning Bolt", "Goblin Guide"]
+        );
+        assert_eq!(plan.cards[0].shortfall(), 2);
+        assert_eq!(plan.cards[1].shortfall(), 6);
+        assert_eq!(plan.cards[2].shortfall(), 0);
+    }
+}
@@ -0,0 +1,723 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use sqlx::{sqlite::Sqlite, QueryBuilder};
+
+use crate::{
+    card::{Currency, COLOURS},
+    utils::{escape_like, normalise_name},
+};
+
+// Rarities from lowest to highest, in the order Scryfall compares them
+const RARITIES: [&str; 6] = ["common", "uncommon", "rare", "special", "mythic", "bonus"];
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SortOrder {
+    /// Best text match first (falls back to name when there is no text to match)
+    Relevance,
+    Name,
+    Price,
+    Cmc,
+    Rarity,
+    Set,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Colon,
+    Eq,
+    NotEq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+impl Operator {
+    // Longer operators first so `<=` isn't read as `<`
+    const ALL: [(&'static str, Operator); 7] = [
+        ("<=", Operator::Lte),
+        (">=", Operator::Gte),
+        ("!=", Operator::NotEq),
+        (":", Operator::Colon),
+        ("=", Operator::Eq),
+        ("<", Operator::Lt),
+        (">", Operator::Gt),
+    ];
+
+    fn to_sql(self) -> &'static str {
+        match self {
+            Self::Colon | Self::Eq => "=",
+            Self::NotEq => "!=",
+            Self::Lt => "<",
+            Self::Lte => "<=",
+            Self::Gt => ">",
+            Self::Gte => ">=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Legality {
+    Legal,
+    Banned,
+    Restricted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Name,
+    Colour,
+    Identity,
+    Type,
+    Oracle,
+    Cmc,
+    Rarity,
+    Set,
+    Price(Currency),
+    Format(Legality),
+    Artist,
+}
+
+impl Field {
+    fn from_keyword(keyword: &str) -> Option<Self> {
+        let field = match keyword.to_lowercase().as_str() {
+            "name" | "n" => Self::Name,
+            "c" | "color" | "colour" => Self::Colour,
+            "id" | "ci" | "identity" => Self::Identity,
+            "t" | "type" => Self::Type,
+            "o" | "oracle" => Self::Oracle,
+            "cmc" | "mv" | "manavalue" => Self::Cmc,
+            "r" | "rarity" => Self::Rarity,
+            "s" | "e" | "set" | "edition" => Self::Set,
+            "eur" | "euro" => Self::Price(Currency::Euro),
+            "eur_foil" | "euro_foil" => Self::Price(Currency::EuroFoil),
+            "usd" => Self::Price(Currency::Usd),
+            "usd_foil" => Self::Price(Currency::UsdFoil),
+            "usd_etched" => Self::Price(Currency::UsdEtched),
+            "tix" => Self::Price(Currency::Tix),
+            "f" | "format" | "legal" => Self::Format(Legality::Legal),
+            "banned" => Self::Format(Legality::Banned),
+            "restricted" => Self::Format(Legality::Restricted),
+            "a" | "artist" => Self::Artist,
+            _ => return None,
+        };
+
+        Some(field)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Filter {
+    field: Field,
+    operator: Operator,
+    value: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Filter(Filter),
+    /// Free text matched against the full text index
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    Not,
+    Or,
+    Term(String),
+}
+
+/// A parsed Scryfall style search query, e.g. `c:r t:instant cmc<=2 usd<0.5 f:pauper`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SearchQuery {
+    expr: Expr,
+}
+
+impl SearchQuery {
+    pub(crate) fn parse(query: &str) -> Result<Self> {
+        let tokens = tokenise(query)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = if parser.tokens.is_empty() {
+            Expr::And(Vec::new())
+        } else {
+            parser.parse_or()?
+        };
+
+        if let Some(token) = parser.peek() {
+            anyhow::bail!("unexpected {token:?} in search query");
+        }
+
+        Ok(Self { expr })
+    }
+
+    /// Restricts the query to cards whose type line contains `card_type`
+    pub(crate) fn with_type(self, card_type: &str) -> Self {
+        let filter = Expr::Filter(Filter {
+            field: Field::Type,
+            operator: Operator::Colon,
+            value: card_type.to_string(),
+        });
+
+        Self {
+            expr: Expr::And(vec![self.expr, filter]),
+        }
+    }
+
+    /// Pushes the query as a SQL condition against the `cards` table aliased as `c`
+    pub(crate) fn push_condition(&self, query: &mut QueryBuilder<'_, Sqlite>) -> Result<()> {
+        push_expr(&self.expr, query)
+    }
+
+    /// Combined full text query for ranking results, if the query contains any free text.
+    /// Keeps the `and` / `or` structure of the query so each result matches it
+    pub(crate) fn text_match(&self) -> Option<String> {
+        text_match(&self.expr)
+    }
+}
+
+/// SQL expression ranking a rarity column from lowest to highest, with unknown
+/// rarities above all others
+pub(crate) fn rarity_rank_sql(column: &str) -> String {
+    let cases: String = RARITIES
+        .iter()
+        .enumerate()
+        .map(|(rank, rarity)| format!(" when '{rarity}' then {rank}"))
+        .collect();
+
+    format!("(case {column}{cases} else {} end)", RARITIES.len())
+}
+
+/// Quotes user input as a single FTS5 phrase so its syntax characters are matched literally
+pub(crate) fn fts_phrase(text: &str) -> String {
+    format!("\"{}\"", normalise_name(text).replace('"', "\"\""))
+}
+
+fn text_match(expr: &Expr) -> Option<String> {
+    let (exprs, joiner) = match expr {
+        Expr::And(exprs) => (exprs, " AND "),
+        Expr::Or(exprs) => (exprs, " OR "),
+        Expr::Text(text) => return Some(fts_phrase(text)),
+        // Negated text shouldn't improve a card's ranking
+        Expr::Not(_) | Expr::Filter(_) => return None,
+    };
+
+    let matches: Vec<String> = exprs.iter().filter_map(text_match).collect();
+    match matches.len() {
+        0 => None,
+        1 => matches.into_iter().next(),
+        _ => Some(format!("({})", matches.join(joiner))),
+    }
+}
+
+fn tokenise(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '-' => {
+                chars.next();
+                tokens.push(Token::Not);
+            }
+            _ => {
+                let mut term = String::new();
+                let mut quoted = false;
+                while let Some(&c) = chars.peek() {
+                    if !quoted && (c.is_whitespace() || c == '(' || c == ')') {
+                        break;
+                    }
+
+                    if c == '"' {
+                        quoted = !quoted;
+                    }
+
+                    term.push(c);
+                    chars.next();
+                }
+
+                if quoted {
+                    anyhow::bail!("unterminated quote in search query");
+                }
+
+                if term.eq_ignore_ascii_case("or") {
+                    tokens.push(Token::Or);
+                } else if !term.eq_ignore_ascii_case("and") {
+                    tokens.push(Token::Term(term));
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut exprs = vec![self.parse_and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            exprs.push(self.parse_and()?);
+        }
+
+        if exprs.len() == 1 {
+            return Ok(exprs.remove(0));
+        }
+
+        Ok(Expr::Or(exprs))
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut exprs: Vec<Expr> = Vec::new();
+        while !matches!(self.peek(), None | Some(Token::Or) | Some(Token::RParen)) {
+            let expr = self.parse_unary()?;
+
+            // Consecutive free text words are matched together as a phrase
+            if let (Expr::Text(text), Some(Expr::Text(previous))) = (&expr, exprs.last_mut()) {
+                previous.push(' ');
+                previous.push_str(text);
+                continue;
+            }
+
+            exprs.push(expr);
+        }
+
+        match exprs.len() {
+            0 => anyhow::bail!("expected a search term"),
+            1 => Ok(exprs.remove(0)),
+            _ => Ok(Expr::And(exprs)),
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                if self.next() != Some(Token::RParen) {
+                    anyhow::bail!("missing closing ')' in search query");
+                }
+
+                Ok(expr)
+            }
+            Some(Token::Term(term)) => parse_term(&term),
+            token => anyhow::bail!("unexpected {token:?} in search query"),
+        }
+    }
+}
+
+fn parse_term(term: &str) -> Result<Expr> {
+    let keyword_end = term
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(term.len());
+
+    let (keyword, rest) = term.split_at(keyword_end);
+    let operator = Operator::ALL
+        .iter()
+        .find(|(symbol, _)| rest.starts_with(symbol));
+
+    let Some((symbol, operator)) = operator.filter(|_| !keyword.is_empty()) else {
+        return Ok(Expr::Text(unquote(term)));
+    };
+
+    let Some(field) = Field::from_keyword(keyword) else {
+        anyhow::bail!("unknown search field '{keyword}'");
+    };
+
+    let value = unquote(&rest[symbol.len()..]);
+    if value.is_empty() {
+        anyhow::bail!("missing value for '{keyword}' in search query");
+    }
+
+    Ok(Expr::Filter(Filter {
+        field,
+        operator: *operator,
+        value,
+    }))
+}
+
+fn unquote(value: &str) -> String {
+    value.replace('"', "")
+}
+
+fn push_expr(expr: &Expr, query: &mut QueryBuilder<'_, Sqlite>) -> Result<()> {
+    match expr {
+        Expr::And(exprs) | Expr::Or(exprs) => {
+            if exprs.is_empty() {
+                query.push("1");
+                return Ok(());
+            }
+
+            let joiner = if matches!(expr, Expr::And(_)) {
+                " and "
+            } else {
+                " or "
+            };
+
+            query.push("(");
+            for (idx, expr) in exprs.iter().enumerate() {
+                if idx > 0 {
+                    query.push(joiner);
+                }
+                push_expr(expr, query)?;
+            }
+            query.push(")");
+        }
+        Expr::Not(expr) => {
+            query.push("not (");
+            push_expr(expr, query)?;
+            query.push(")");
+        }
+        Expr::Text(text) => {
+            query
+                .push("c.rowid in (select rowid from cards_fts where cards_fts match ")
+                .push_bind(fts_phrase(text))
+                .push(")");
+        }
+        Expr::Filter(filter) => push_filter(filter, query)?,
+    }
+
+    Ok(())
+}
+
+fn push_filter(filter: &Filter, query: &mut QueryBuilder<'_, Sqlite>) -> Result<()> {
+    let Filter {
+        field,
+        operator,
+        value,
+    } = filter;
+
+    match field {
+        Field::Name => push_like(
+            "c.normalised_name",
+            &normalise_name(value),
+            *operator,
+            query,
+        )?,
+        Field::Type => push_like("c.type_line", value, *operator, query)?,
+        Field::Oracle => push_like("c.oracle_text", value, *operator, query)?,
+        Field::Artist => push_like("c.artist", value, *operator, query)?,
+        Field::Colour => push_colours("c.colors", value, *operator, query)?,
+        Field::Identity => push_colours("c.color_identity", value, *operator, query)?,
+        Field::Cmc => {
+            query
+                .push("c.cmc ")
+                .push(operator.to_sql())
+                .push(" ")
+                .push_bind(parse_number(value)?);
+        }
+        Field::Price(currency) => {
+            query
+                .push("c.")
+                .push(currency.column())
+                .push(" ")
+                .push(operator.to_sql())
+                .push(" ")
+                .push_bind(parse_number(value)?);
+        }
+        Field::Set => {
+            equality_only(*operator, "set")?;
+            query
+                .push("c.set_tag ")
+                .push(operator.to_sql())
+                .push(" ")
+                .push_bind(value.to_uppercase());
+        }
+        Field::Rarity => {
+            let Some(rank) = rarity_rank(value) else {
+                anyhow::bail!("unknown rarity '{value}'");
+            };
+
+            query
+                .push(rarity_rank_sql("c.rarity"))
+                .push(" ")
+                .push(operator.to_sql())
+                .push(" ")
+                .push_bind(rank as i64);
+        }
+        Field::Format(legality) => {
+            equality_only(*operator, "format")?;
+            let statuses = match legality {
+                // Restricted cards can still be played in the format
+                Legality::Legal => "('legal', 'restricted')",
+                Legality::Banned => "('banned')",
+                Legality::Restricted => "('restricted')",
+            };
+
+            if *operator == Operator::NotEq {
+                query.push("not ");
+            }
+
+            query
+                .push("coalesce(json_extract(c.legalities, '$.' || ")
+                .push_bind(value.to_lowercase())
+                .push("), '') in ")
+                .push(statuses);
+        }
+    }
+
+    Ok(())
+}
+
+fn push_like(
+    column: &'static str,
+    value: &str,
+    operator: Operator,
+    query: &mut QueryBuilder<'_, Sqlite>,
+) -> Result<()> {
+    equality_only(operator, column.trim_start_matches("c."))?;
+    if operator == Operator::NotEq {
+        query.push("not ");
+    }
+
+    query
+        .push("coalesce(")
+        .push(column)
+        .push(", '') like '%' || ")
+        .push_bind(escape_like(value))
+        .push(r" || '%' escape '\'");
+
+    Ok(())
+}
+
+fn push_colours(
+    column: &'static str,
+    value: &str,
+    operator: Operator,
+    query: &mut QueryBuilder<'_, Sqlite>,
+) -> Result<()> {
+    let column = format!("coalesce({column}, '')");
+    let value = value.to_lowercase();
+
+    if matches!(value.as_str(), "m" | "multi" | "multicolor" | "multicolour") {
+        equality_only(operator, "multicolour")?;
+        let condition = if operator == Operator::NotEq {
+            "<"
+        } else {
+            ">="
+        };
+        query.push(format!("length({column}) {condition} 2"));
+        return Ok(());
+    }
+
+    let colours = parse_colours(&value)?;
+    let includes_all = colours
+        .iter()
+        .map(|colour| format!("instr({column}, '{colour}') > 0"))
+        .collect::<Vec<String>>();
+    let excludes_others = COLOURS
+        .iter()
+        .filter(|colour| !colours.contains(colour))
+        .map(|colour| format!("instr({column}, '{colour}') = 0"))
+        .collect::<Vec<String>>();
+    let count = colours.len();
+
+    // `c:` matches cards with at least the given colours (colourless is exact)
+    let mut conditions = match operator {
+        Operator::Colon | Operator::Gte if count == 0 => vec![format!("{column} = ''")],
+        Operator::Colon | Operator::Gte => includes_all,
+        Operator::Eq | Operator::NotEq => [includes_all, excludes_others].concat(),
+        Operator::Gt => [includes_all, vec![format!("length({column}) > {count}")]].concat(),
+        Operator::Lte => excludes_others,
+        Operator::Lt => [excludes_others, vec![format!("length({column}) < {count}")]].concat(),
+    };
+
+    if conditions.is_empty() {
+        conditions.push("1".to_string());
+    }
+
+    let condition = conditions.join(" and ");
+    if operator == Operator::NotEq {
+        query.push(format!("not ({condition})"));
+    } else {
+        query.push(format!("({condition})"));
+    }
+
+    Ok(())
+}
+
+fn parse_colours(value: &str) -> Result<Vec<char>> {
+    let colours = match value {
+        "c" | "colorless" | "colourless" => String::new(),
+        "white" => "w".to_string(),
+        "blue" => "u".to_string(),
+        "black" => "b".to_string(),
+        "red" => "r".to_string(),
+        "green" => "g".to_string(),
+        value => value.to_string(),
+    };
+
+    let mut parsed = Vec::new();
+    for colour in colours.to_uppercase().chars() {
+        if !COLOURS.contains(&colour) {
+            anyhow::bail!("unknown colour '{value}'");
+        }
+
+        if !parsed.contains(&colour) {
+            parsed.push(colour);
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn rarity_rank(value: &str) -> Option<usize> {
+    let rarity = match value.to_lowercase().as_str() {
+        "c" => "common",
+        "u" => "uncommon",
+        "r" => "rare",
+        "m" => "mythic",
+        "s" => "special",
+        "b" => "bonus",
+        _ => return RARITIES.iter().position(|r| r.eq_ignore_ascii_case(value)),
+    };
+
+    RARITIES.iter().position(|r| *r == rarity)
+}
+
+fn parse_number(value: &str) -> Result<f64> {
+    value
+        .parse::<f64>()
+        .map_err(|_| anyhow::anyhow!("expected a number but found '{value}'"))
+}
+
+fn equality_only(operator: Operator, field: &str) -> Result<()> {
+    if !matches!(operator, Operator::Colon | Operator::Eq | Operator::NotEq) {
+        anyhow::bail!("'{field}' only supports ':', '=' and '!=' comparisons");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(field: Field, operator: Operator, value: &str) -> Expr {
+        Expr::Filter(Filter {
+            field,
+            operator,
+            value: value.to_string(),
+        })
+    }
+
+    fn parse(query: &str) -> Expr {
+        SearchQuery::parse(query).unwrap().expr
+    }
+
+    #[test]
+    fn parses_filters_and_text() {
+        let expected = Expr::And(vec![
+            filter(Field::Colour, Operator::Colon, "r"),
+            filter(Field::Cmc, Operator::Lte, "2"),
+            filter(Field::Price(Currency::Usd), Operator::Lt, "0.5"),
+            Expr::Text("lightning bolt".to_string()),
+        ]);
+
+        assert_eq!(parse("c:r cmc<=2 usd<0.5 lightning bolt"), expected);
+        assert_eq!(
+            parse(r#"o:"draw a card""#),
+            filter(Field::Oracle, Operator::Colon, "draw a card")
+        );
+    }
+
+    #[test]
+    fn or_binds_looser_than_and() {
+        let expected = Expr::Or(vec![
+            Expr::And(vec![
+                Expr::Text("bolt".to_string()),
+                filter(Field::Colour, Operator::Colon, "r"),
+            ]),
+            Expr::Text("helix".to_string()),
+        ]);
+
+        assert_eq!(parse("bolt c:r or helix"), expected);
+        assert_eq!(parse("bolt and c:r OR helix"), expected);
+    }
+
+    #[test]
+    fn parses_negation_and_groups() {
+        let expected = Expr::And(vec![
+            filter(Field::Type, Operator::Colon, "creature"),
+            Expr::Not(Box::new(Expr::Or(vec![
+                filter(Field::Colour, Operator::Colon, "g"),
+                filter(Field::Rarity, Operator::NotEq, "common"),
+            ]))),
+        ]);
+
+        assert_eq!(parse("t:creature -(c:g or r!=common)"), expected);
+        assert_eq!(parse(""), Expr::And(Vec::new()));
+    }
+
+    #[test]
+    fn rejects_invalid_queries() {
+        for query in [
+            "foo:bar",
+            "c:",
+            r#"o:"draw"#,
+            "(c:r",
+            "c:r)",
+            "()",
+            "c:r or",
+        ] {
+            assert!(SearchQuery::parse(query).is_err(), "parsed '{query}'");
+        }
+    }
+
+    #[test]
+    fn text_match_keeps_query_structure() {
+        let text_match = |query: &str| SearchQuery::parse(query).unwrap().text_match();
+
+        assert_eq!(text_match("bolt"), Some(r#""bolt""#.to_string()));
+        assert_eq!(
+            text_match("bolt or helix"),
+            Some(r#"("bolt" OR "helix")"#.to_string())
+        );
+        assert_eq!(
+            text_match("fire (ice or c:r) -bolt"),
+            Some(r#"("fire" AND "ice")"#.to_string())
+        );
+        assert_eq!(text_match("c:r or t:instant"), None);
+    }
+
+    #[test]
+    fn ranks_rarities_like_scryfall() {
+        let ranks: Vec<Option<usize>> = ["c", "uncommon", "r", "s", "mythic", "b"]
+            .iter()
+            .map(|rarity| rarity_rank(rarity))
+            .collect();
+
+        assert_eq!(ranks, (0..6).map(Some).collect::<Vec<Option<usize>>>());
+        assert_eq!(rarity_rank("legendary"), None);
+    }
+}
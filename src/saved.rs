@@ -0,0 +1,101 @@
+use sqlx::FromRow;
+
+use crate::card::Currency;
+
+/// Currencies saved deck values are recorded in on each sync
+pub(crate) const VALUE_CURRENCIES: [Currency; 3] = [Currency::Euro, Currency::Usd, Currency::Tix];
+
+/// Default change in value, as a percentage, for a saved deck to be listed after a sync
+pub(crate) const VALUE_CHANGE_THRESHOLD: f32 = 10.0;
+
+/// A deck saved in the database, with its latest revision
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub(crate) struct SavedDeck {
+    pub(crate) name: String,
+    pub(crate) revisions: u32,
+    pub(crate) saved_at: String,
+    pub(crate) cards: u32,
+}
+
+impl std::fmt::Display for SavedDeck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let plural = if self.revisions == 1 { "" } else { "s" };
+        write!(
+            f,
+            "{} - {} cards, {} revision{plural} (last saved {})",
+            self.name, self.cards, self.revisions, self.saved_at
+        )
+    }
+}
+
+/// A single saved revision of a deck
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub(crate) struct DeckRevision {
+    pub(crate) revision: u32,
+    pub(crate) saved_at: String,
+    pub(crate) cards: u32,
+}
+
+impl std::fmt::Display for DeckRevision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "revision {} - {} cards, saved {}",
+            self.revision, self.cards, self.saved_at
+        )
+    }
+}
+
+/// Value of a saved deck recorded during a sync
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub(crate) struct DeckValue {
+    pub(crate) revision: u32,
+    pub(crate) value: f32,
+    /// Copies of cards left out of the value, as they had no price or weren't found
+    pub(crate) unpriced: u32,
+    pub(crate) recorded_at: String,
+}
+
+/// Change between two values as a percentage, `None` when the old value is zero
+pub(crate) fn percent_change(old: f32, new: f32) -> Option<f32> {
+    (old > 0.0).then(|| (new - old) / old * 100.0)
+}
+
+/// Splits a saved deck reference into its name and revision, e.g. `burn@2`.
+/// References without a revision are for the latest one
+pub(crate) fn parse_reference(reference: &str) -> (&str, Option<u32>) {
+    match reference.rsplit_once('@') {
+        Some((name, revision)) if !name.is_empty() => match revision.parse::<u32>() {
+            Ok(revision) => (name, Some(revision)),
+            Err(_) => (reference, None),
+        },
+        _ => (reference, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_references() {
+        assert_eq!(parse_reference("burn"), ("burn", None));
+        assert_eq!(parse_reference("burn@2"), ("burn", Some(2)));
+        assert_eq!(parse_reference("burn@home@3"), ("burn@home", Some(3)));
+    }
+
+    #[test]
+    fn keeps_references_without_a_revision_whole() {
+        assert_eq!(parse_reference("burn@"), ("burn@", None));
+        assert_eq!(parse_reference("burn@latest"), ("burn@latest", None));
+        assert_eq!(parse_reference("burn@-1"), ("burn@-1", None));
+        assert_eq!(parse_reference("@2"), ("@2", None));
+    }
+
+    #[test]
+    fn calculates_percent_changes() {
+        assert_eq!(percent_change(50.0, 60.0), Some(20.0));
+        assert_eq!(percent_change(80.0, 60.0), Some(-25.0));
+        assert_eq!(percent_change(0.0, 60.0), None);
+    }
+}
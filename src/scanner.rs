@@ -0,0 +1,330 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+
+use crate::{
+    collection::{Condition, Finish},
+    utils::normalise_header,
+};
+
+/// Card scanning apps with a CSV collection export
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ScannerApp {
+    Manabox,
+    Delver,
+    #[clap(name = "dragonshield")]
+    DragonShield,
+    Tcgplayer,
+}
+
+/// Column names an app uses for each field, normalised with `normalise_header`
+/// and tried in order
+#[derive(Debug)]
+struct ColumnNames {
+    name: &'static [&'static str],
+    quantity: &'static [&'static str],
+    set: &'static [&'static str],
+    collector_number: &'static [&'static str],
+    scryfall_id: &'static [&'static str],
+    finish: &'static [&'static str],
+    condition: &'static [&'static str],
+    language: &'static [&'static str],
+}
+
+const MANABOX: ColumnNames = ColumnNames {
+    name: &["name"],
+    quantity: &["quantity"],
+    set: &["setcode"],
+    collector_number: &["collectornumber"],
+    scryfall_id: &["scryfallid"],
+    finish: &["foil"],
+    condition: &["condition"],
+    language: &["language"],
+};
+
+const DELVER: ColumnNames = ColumnNames {
+    name: &["name"],
+    quantity: &["quantityx", "quantity", "count"],
+    set: &["editioncode", "setcode"],
+    collector_number: &["collectorsnumber", "collectornumber", "number"],
+    scryfall_id: &["scryfallid"],
+    finish: &["foil", "finish"],
+    condition: &["condition"],
+    language: &["language"],
+};
+
+const DRAGON_SHIELD: ColumnNames = ColumnNames {
+    name: &["cardname"],
+    quantity: &["quantity"],
+    set: &["setcode"],
+    collector_number: &["cardnumber"],
+    scryfall_id: &[],
+    finish: &["printing"],
+    condition: &["condition"],
+    language: &["language"],
+};
+
+// "Simple Name" leaves out the variant TCGplayer adds to the name, e.g. "(Borderless)"
+const TCGPLAYER: ColumnNames = ColumnNames {
+    name: &["simplename", "name"],
+    quantity: &["quantity"],
+    set: &["setcode"],
+    collector_number: &["cardnumber"],
+    scryfall_id: &[],
+    finish: &["printing"],
+    condition: &["condition"],
+    language: &["language"],
+};
+
+// Languages spelled out by the apps, mapped to Scryfall's language codes
+const LANGUAGES: [(&str, &str); 12] = [
+    ("english", "en"),
+    ("spanish", "es"),
+    ("french", "fr"),
+    ("german", "de"),
+    ("italian", "it"),
+    ("portuguese", "pt"),
+    ("japanese", "ja"),
+    ("korean", "ko"),
+    ("russian", "ru"),
+    ("chinesesimplified", "zhs"),
+    ("chinesetraditional", "zht"),
+    ("phyrexian", "ph"),
+];
+
+impl ScannerApp {
+    fn columns(self) -> &'static ColumnNames {
+        match self {
+            Self::Manabox => &MANABOX,
+            Self::Delver => &DELVER,
+            Self::DragonShield => &DRAGON_SHIELD,
+            Self::Tcgplayer => &TCGPLAYER,
+        }
+    }
+}
+
+impl std::fmt::Display for ScannerApp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let app = match self {
+            Self::Manabox => "ManaBox",
+            Self::Delver => "Delver Lens",
+            Self::DragonShield => "Dragon Shield",
+            Self::Tcgplayer => "TCGplayer",
+        };
+
+        write!(f, "{app}")
+    }
+}
+
+/// A row of a scanner export, before it's matched to a card
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ScannedCard {
+    /// Line of the row in the file, for reporting rows that can't be matched
+    pub(crate) line: u64,
+    pub(crate) name: String,
+    pub(crate) quantity: u32,
+    pub(crate) set: String,
+    pub(crate) collector_number: String,
+    pub(crate) scryfall_id: Option<String>,
+    pub(crate) finish: Finish,
+    pub(crate) condition: Condition,
+    pub(crate) language: String,
+}
+
+/// How a row of a scanner export was matched to a synced card
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MatchMethod {
+    ScryfallId,
+    /// Set code and collector number
+    Printing,
+    Name,
+}
+
+impl std::fmt::Display for ScannedCard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}x {}", self.line, self.quantity, self.name)?;
+        if !self.set.is_empty() {
+            write!(f, " ({} {})", self.set, self.collector_number)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Columns {
+    name: usize,
+    quantity: Option<usize>,
+    set: Option<usize>,
+    collector_number: Option<usize>,
+    scryfall_id: Option<usize>,
+    finish: Option<usize>,
+    condition: Option<usize>,
+    language: Option<usize>,
+}
+
+impl Columns {
+    fn from_headers(headers: &::csv::StringRecord, app: ScannerApp) -> Result<Self> {
+        let headers: Vec<String> = headers.iter().map(normalise_header).collect();
+        let find = |aliases: &[&str]| {
+            aliases
+                .iter()
+                .find_map(|alias| headers.iter().position(|header| header == alias))
+        };
+
+        let names = app.columns();
+        let Some(name) = find(names.name) else {
+            anyhow::bail!("no card name column found in {app} export");
+        };
+
+        Ok(Self {
+            name,
+            quantity: find(names.quantity),
+            set: find(names.set),
+            collector_number: find(names.collector_number),
+            scryfall_id: find(names.scryfall_id),
+            finish: find(names.finish),
+            condition: find(names.condition),
+            language: find(names.language),
+        })
+    }
+}
+
+/// Parses a CSV collection export from a card scanning app
+pub(crate) fn parse(content: &str, app: ScannerApp) -> Result<Vec<ScannedCard>> {
+    // Dragon Shield starts its exports with an Excel separator hint, which is
+    // skipped while keeping line numbers relative to the file
+    let content = content.trim_start_matches('\u{feff}');
+    let (content, skipped_lines) = match content.split_once('\n') {
+        Some((first, rest)) if first.trim().trim_matches('"').starts_with("sep=") => (rest, 1),
+        _ => (content, 0),
+    };
+
+    let mut reader = ::csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(::csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    let columns = Columns::from_headers(reader.headers().context("reading csv headers")?, app)?;
+    let mut cards = Vec::new();
+    for record in reader.records() {
+        let record = record.context("reading csv record")?;
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|idx| record.get(idx))
+                .filter(|value| !value.is_empty())
+        };
+
+        let Some(name) = field(Some(columns.name)) else {
+            continue;
+        };
+
+        let quantity = field(columns.quantity)
+            .and_then(|quantity| quantity.parse::<u32>().ok())
+            .unwrap_or(1);
+
+        if quantity == 0 {
+            continue;
+        }
+
+        cards.push(ScannedCard {
+            line: record.position().map(|pos| pos.line()).unwrap_or_default() + skipped_lines,
+            name: name.to_string(),
+            quantity,
+            set: field(columns.set).unwrap_or_default().to_uppercase(),
+            collector_number: field(columns.collector_number)
+                .unwrap_or_default()
+                .to_string(),
+            scryfall_id: field(columns.scryfall_id).map(|id| id.to_lowercase()),
+            finish: field(columns.finish).map_or(Finish::Nonfoil, parse_finish),
+            condition: field(columns.condition).map_or(Condition::NearMint, parse_condition),
+            language: field(columns.language).map_or_else(|| "en".to_string(), parse_language),
+        });
+    }
+
+    Ok(cards)
+}
+
+fn parse_finish(value: &str) -> Finish {
+    let value = normalise_header(value);
+    let foil = value.contains("foil") && !value.contains("non");
+    if value.contains("etched") {
+        Finish::Etched
+    } else if foil || matches!(value.as_str(), "true" | "yes" | "1") {
+        Finish::Foil
+    } else {
+        Finish::Nonfoil
+    }
+}
+
+/// Maps the apps' condition names to the TCGplayer scale, with Cardmarket grades
+/// (excellent, good, played, poor) taken as the closest TCGplayer grade.
+/// Unknown conditions are taken as near mint
+fn parse_condition(value: &str) -> Condition {
+    let value = normalise_header(value);
+    if value.starts_with("nearmint") || value == "nm" {
+        Condition::NearMint
+    } else if value.starts_with("mint") || value == "m" {
+        Condition::Mint
+    } else if value.starts_with("excellent")
+        || value.starts_with("light")
+        || value.starts_with("slightly")
+        || value == "lp"
+        || value == "ex"
+    {
+        Condition::LightlyPlayed
+    } else if value.starts_with("good") || value.starts_with("moderately") || value == "mp" {
+        Condition::ModeratelyPlayed
+    } else if value.starts_with("played") || value.starts_with("heavily") || value == "hp" {
+        Condition::HeavilyPlayed
+    } else if value.starts_with("poor") || value.starts_with("damaged") || value == "dmg" {
+        Condition::Damaged
+    } else {
+        Condition::NearMint
+    }
+}
+
+fn parse_language(value: &str) -> String {
+    let normalised = normalise_header(value);
+    LANGUAGES
+        .iter()
+        .find(|(language, _)| *language == normalised)
+        .map(|(_, code)| code.to_string())
+        .unwrap_or(normalised)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_finishes() {
+        for (value, finish) in [
+            ("Foil", Finish::Foil),
+            ("yes", Finish::Foil),
+            ("TRUE", Finish::Foil),
+            ("1", Finish::Foil),
+            ("Etched Foil", Finish::Etched),
+            ("Non-Foil", Finish::Nonfoil),
+            ("Normal", Finish::Nonfoil),
+            ("no", Finish::Nonfoil),
+            ("0", Finish::Nonfoil),
+        ] {
+            assert_eq!(parse_finish(value), finish, "{value}");
+        }
+    }
+
+    #[test]
+    fn parses_dragon_shield_export() {
+        let content = "\u{feff}\"sep=,\"\nQuantity,Card Name,Set Code,Card Number,Printing,Condition,Language\n2,Lightning Bolt,m10,146,Foil,Excellent,English\n0,Opt,xln,65,Normal,NearMint,English\n1,Opt,XLN,65,Normal,Good,Japanese\n";
+        let cards = parse(content, ScannerApp::DragonShield).unwrap();
+
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].to_string(), "line 3: 2x Lightning Bolt (M10 146)");
+        assert_eq!(cards[0].finish, Finish::Foil);
+        assert_eq!(cards[0].condition, Condition::LightlyPlayed);
+        assert_eq!(cards[0].language, "en");
+        assert_eq!(cards[1].line, 5);
+        assert_eq!(cards[1].condition, Condition::ModeratelyPlayed);
+        assert_eq!(cards[1].language, "ja");
+    }
+}
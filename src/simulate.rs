@@ -0,0 +1,261 @@
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+const OPENING_HAND: usize = 7;
+/// London mulligans stop once the kept hand would drop below this size
+const MIN_HAND: usize = 5;
+
+/// A card in the library, with the index of the deck entry it came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LibraryCard {
+    entry: usize,
+    is_land: bool,
+}
+
+/// Hands with a land count in this range are kept when mulliganing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct KeepRange {
+    pub(crate) min_lands: usize,
+    pub(crate) max_lands: usize,
+}
+
+impl KeepRange {
+    fn keeps(&self, lands: usize) -> bool {
+        (self.min_lands..=self.max_lands).contains(&lands)
+    }
+}
+
+/// Results of a Monte Carlo run over shuffled libraries
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SimulationResult {
+    pub(crate) trials: u32,
+    /// Opening sevens by number of lands (index 0 to 7)
+    pub(crate) opening_lands: [u32; OPENING_HAND + 1],
+    /// Kept hands by number of mulligans taken (7, 6 or 5 cards)
+    pub(crate) mulligans: [u32; OPENING_HAND - MIN_HAND + 1],
+    /// Trials where each tracked entry was seen by the target turn
+    pub(crate) seen_by_turn: Vec<u32>,
+}
+
+impl SimulationResult {
+    pub(crate) fn rate(&self, count: u32) -> f64 {
+        count as f64 / self.trials as f64
+    }
+
+    pub(crate) fn at_least_lands(&self, lands: usize) -> f64 {
+        let count = self.opening_lands.iter().skip(lands).sum();
+        self.rate(count)
+    }
+}
+
+/// Shuffles a library built from `(entry index, copies, is land)` triples
+#[derive(Debug)]
+pub(crate) struct Simulator {
+    library: Vec<LibraryCard>,
+    rng: StdRng,
+}
+
+impl Simulator {
+    pub(crate) fn new(cards: &[(usize, u32, bool)], seed: u64) -> Self {
+        let library = cards
+            .iter()
+            .flat_map(|(entry, copies, is_land)| {
+                let card = LibraryCard {
+                    entry: *entry,
+                    is_land: *is_land,
+                };
+                (0..*copies).map(move |_| card)
+            })
+            .collect();
+
+        Self {
+            library,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub(crate) fn library_size(&self) -> usize {
+        self.library.len()
+    }
+
+    /// Runs `trials` shuffles, tracking opening hand lands, London mulligans and
+    /// whether each of `tracked` entries has been seen within `cards_seen` cards
+    pub(crate) fn run(
+        &mut self,
+        trials: u32,
+        keep: KeepRange,
+        tracked: &[usize],
+        cards_seen: usize,
+    ) -> SimulationResult {
+        let mut result = SimulationResult {
+            trials,
+            opening_lands: [0; OPENING_HAND + 1],
+            mulligans: [0; OPENING_HAND - MIN_HAND + 1],
+            seen_by_turn: vec![0; tracked.len()],
+        };
+
+        let mut library = self.library.clone();
+        for _ in 0..trials {
+            library.shuffle(&mut self.rng);
+            let opening_lands = count_lands(&library[..OPENING_HAND.min(library.len())]);
+            result.opening_lands[opening_lands] += 1;
+
+            let seen = &library[..cards_seen.min(library.len())];
+            for (count, entry) in result.seen_by_turn.iter_mut().zip(tracked) {
+                if seen.iter().any(|card| card.entry == *entry) {
+                    *count += 1;
+                }
+            }
+
+            // London mulligan: draw a fresh seven each time and bottom one card per
+            // mulligan, so a hand of seven with `n` cards to bottom is kept when the
+            // best `7 - n` cards have a keepable land count
+            let mut mulligans = 0;
+            loop {
+                let hand = &library[..OPENING_HAND.min(library.len())];
+                let kept_size = OPENING_HAND - mulligans;
+                if kept_size <= MIN_HAND || can_keep(hand, kept_size, keep) {
+                    break;
+                }
+
+                mulligans += 1;
+                library.shuffle(&mut self.rng);
+            }
+            result.mulligans[mulligans] += 1;
+        }
+
+        result
+    }
+}
+
+fn count_lands(cards: &[LibraryCard]) -> usize {
+    cards.iter().filter(|card| card.is_land).count()
+}
+
+/// Whether some `kept_size` cards of a hand have a land count in the keep range
+fn can_keep(hand: &[LibraryCard], kept_size: usize, keep: KeepRange) -> bool {
+    let lands = count_lands(hand);
+    let spells = hand.len() - lands;
+    let bottomed = hand.len().saturating_sub(kept_size);
+
+    // Bottoming can drop anywhere between 0 and `bottomed` lands
+    (0..=bottomed)
+        .filter(|lands_bottomed| *lands_bottomed <= lands && bottomed - lands_bottomed <= spells)
+        .any(|lands_bottomed| keep.keeps(lands - lands_bottomed))
+}
+
+/// Probability of exactly `k` successes when drawing `n` cards from a deck of
+/// `population` containing `successes` hits
+pub(crate) fn hypergeometric(population: u32, successes: u32, n: u32, k: u32) -> f64 {
+    if k > successes || k > n || n - k > population - successes {
+        return 0.0;
+    }
+
+    choose(successes, k) * choose(population - successes, n - k) / choose(population, n)
+}
+
+/// Probability of at least `k` successes, see [`hypergeometric`]
+pub(crate) fn hypergeometric_at_least(population: u32, successes: u32, n: u32, k: u32) -> f64 {
+    (k..=n.min(successes))
+        .map(|k| hypergeometric(population, successes, n, k))
+        .sum()
+}
+
+fn choose(n: u32, k: u32) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+
+    let k = k.min(n - k);
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEEP: KeepRange = KeepRange {
+        min_lands: 2,
+        max_lands: 4,
+    };
+
+    fn hand(lands: usize, spells: usize) -> Vec<LibraryCard> {
+        let land = LibraryCard {
+            entry: 0,
+            is_land: true,
+        };
+        let spell = LibraryCard {
+            entry: 1,
+            is_land: false,
+        };
+
+        (0..lands)
+            .map(|_| land)
+            .chain((0..spells).map(|_| spell))
+            .collect()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-4, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn counts_combinations() {
+        assert_eq!(choose(60, 7), 386_206_920.0);
+        assert_eq!(choose(52, 5), 2_598_960.0);
+        assert_eq!(choose(5, 0), 1.0);
+        assert_eq!(choose(5, 5), 1.0);
+        assert_eq!(choose(4, 5), 0.0);
+    }
+
+    #[test]
+    fn matches_known_draw_odds() {
+        // At least one of a four-of in an opening seven from 60 cards
+        assert_close(hypergeometric_at_least(60, 4, 7, 1), 0.3995);
+        assert_close(hypergeometric(60, 4, 7, 0), 0.6005);
+        // Exactly three of 24 lands in an opening seven
+        assert_close(hypergeometric(60, 24, 7, 3), 0.3087);
+
+        assert_eq!(hypergeometric(60, 4, 7, 5), 0.0);
+        assert_eq!(hypergeometric(10, 9, 7, 5), 0.0);
+        assert_close((0..=7).map(|k| hypergeometric(60, 24, 7, k)).sum(), 1.0);
+    }
+
+    #[test]
+    fn keeps_hands_after_bottoming() {
+        assert!(can_keep(&hand(3, 4), 7, KEEP));
+        assert!(!can_keep(&hand(1, 6), 7, KEEP));
+        assert!(!can_keep(&hand(5, 2), 7, KEEP));
+        // Bottoming a land from a five land seven after one mulligan
+        assert!(can_keep(&hand(5, 2), 6, KEEP));
+        assert!(!can_keep(&hand(7, 0), 5, KEEP));
+        assert!(!can_keep(&hand(1, 6), 5, KEEP));
+    }
+
+    #[test]
+    fn mulligans_unkeepable_decks_to_five() {
+        let mut simulator = Simulator::new(&[(0, 60, true)], 1);
+        let result = simulator.run(100, KEEP, &[0], 7);
+
+        assert_eq!(simulator.library_size(), 60);
+        assert_eq!(result.opening_lands[7], 100);
+        assert_eq!(result.mulligans, [0, 0, 100]);
+        assert_eq!(result.seen_by_turn, [100]);
+        assert_eq!(result.at_least_lands(7), 1.0);
+    }
+
+    #[test]
+    fn reproduces_runs_from_a_seed() {
+        let deck = [(0, 24, true), (1, 4, false), (2, 32, false)];
+        let run = |seed| Simulator::new(&deck, seed).run(1000, KEEP, &[1], 8);
+
+        let result = run(7);
+        assert_eq!(result, run(7));
+        assert_eq!(result.opening_lands.iter().sum::<u32>(), 1000);
+        assert_eq!(result.mulligans.iter().sum::<u32>(), 1000);
+        // Monte Carlo estimate of seeing a four-of in the top eight cards
+        assert!(
+            (result.rate(result.seen_by_turn[0]) - hypergeometric_at_least(60, 4, 8, 1)).abs()
+                < 0.05
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use serde::Serialize;
+
+use crate::card::{DbCard, Deck, DeckSection, COLOURS};
+
+const CARD_TYPES: [&str; 8] = [
+    "Creature",
+    "Planeswalker",
+    "Battle",
+    "Instant",
+    "Sorcery",
+    "Artifact",
+    "Enchantment",
+    "Land",
+];
+
+/// Mana values at or above this are grouped into a single `7+` bucket
+const CURVE_MAX: usize = 7;
+const CHART_WIDTH: u32 = 40;
+
+/// A labelled count, used for every chart so they keep their order in JSON
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Count {
+    pub(crate) name: String,
+    pub(crate) count: u32,
+}
+
+impl Count {
+    fn new(name: impl Into<String>, count: u32) -> Self {
+        Self {
+            name: name.into(),
+            count,
+        }
+    }
+}
+
+/// Statistics for the main deck and command zone of a deck
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct DeckStats {
+    pub(crate) cards: u32,
+    pub(crate) lands: u32,
+    pub(crate) average_cmc: f32,
+    pub(crate) average_cmc_without_lands: f32,
+    pub(crate) curve: Vec<Count>,
+    pub(crate) pips: Vec<Count>,
+    pub(crate) types: Vec<Count>,
+    pub(crate) keywords: Vec<Count>,
+    /// Deck entries that couldn't be found, which aren't included in the stats
+    pub(crate) unknown: Vec<String>,
+}
+
+impl DeckStats {
+    /// Works out stats for a deck, with `cards` holding the database entry for
+    /// each deck entry in the same order. The sideboard isn't included
+    pub(crate) fn new(deck: &Deck, cards: &[Option<DbCard>]) -> Self {
+        let mut stats = Self::default();
+        let mut curve = [0; CURVE_MAX + 1];
+        let mut pips = [0; COLOURS.len() + 1];
+        let mut types = [0; CARD_TYPES.len()];
+        let mut total_cmc = 0.0;
+        let mut nonland_cmc = 0.0;
+
+        for (entry, card) in deck.cards.iter().zip(cards) {
+            if entry.section == DeckSection::Sideboard {
+                continue;
+            }
+
+            let Some(card) = card else {
+                stats.unknown.push(entry.name.clone());
+                continue;
+            };
+
+            let quantity = entry.quantity;
+            let cmc = card.cmc.unwrap_or_default();
+            stats.cards += quantity;
+            total_cmc += cmc * quantity as f32;
+
+            if card.has_type("Land") {
+                stats.lands += quantity;
+            } else {
+                nonland_cmc += cmc * quantity as f32;
+                curve[(cmc as usize).min(CURVE_MAX)] += quantity;
+            }
+
+            for (count, pip) in pips.iter_mut().zip(count_pips(card)) {
+                *count += pip * quantity;
+            }
+
+            for (count, card_type) in types.iter_mut().zip(CARD_TYPES) {
+                if card.has_type(card_type) {
+                    *count += quantity;
+                }
+            }
+
+            let keywords: Vec<String> = card
+                .keywords
+                .as_deref()
+                .and_then(|keywords| serde_json::from_str(keywords).ok())
+                .unwrap_or_default();
+
+            for keyword in keywords {
+                match stats.keywords.iter_mut().find(|k| k.name == keyword) {
+                    Some(count) => count.count += quantity,
+                    None => stats.keywords.push(Count::new(keyword, quantity)),
+                }
+            }
+        }
+
+        let nonland = stats.cards - stats.lands;
+        if stats.cards > 0 {
+            stats.average_cmc = total_cmc / stats.cards as f32;
+        }
+
+        if nonland > 0 {
+            stats.average_cmc_without_lands = nonland_cmc / nonland as f32;
+        }
+
+        stats.curve = curve
+            .iter()
+            .enumerate()
+            .map(|(cmc, count)| match cmc {
+                CURVE_MAX => Count::new(format!("{CURVE_MAX}+"), *count),
+                _ => Count::new(cmc.to_string(), *count),
+            })
+            .collect();
+
+        stats.pips = COLOURS
+            .iter()
+            .chain(['C'].iter())
+            .zip(pips)
+            .map(|(colour, count)| Count::new(colour.to_string(), count))
+            .collect();
+
+        stats.types = CARD_TYPES
+            .iter()
+            .zip(types)
+            .filter(|(_, count)| *count > 0)
+            .map(|(card_type, count)| Count::new(*card_type, count))
+            .collect();
+
+        stats
+            .keywords
+            .sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+
+        stats
+    }
+}
+
+impl std::fmt::Display for DeckStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "[*] Cards: {} ({} lands)", self.cards, self.lands)?;
+        writeln!(
+            f,
+            "[*] Average mana value: {:.2} ({:.2} including lands)",
+            self.average_cmc_without_lands, self.average_cmc
+        )?;
+
+        let charts = [
+            ("Mana curve", &self.curve),
+            ("Colour pips", &self.pips),
+            ("Card types", &self.types),
+            ("Keywords", &self.keywords),
+        ];
+
+        for (title, counts) in charts {
+            if counts.is_empty() {
+                continue;
+            }
+
+            writeln!(f, "\n[*] {title}")?;
+            write_chart(f, counts)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Draws a horizontal bar chart, scaling bars to the largest count
+fn write_chart(f: &mut std::fmt::Formatter<'_>, counts: &[Count]) -> std::fmt::Result {
+    let label_width = counts
+        .iter()
+        .map(|count| count.name.chars().count())
+        .max()
+        .unwrap_or_default();
+    let max = counts
+        .iter()
+        .map(|count| count.count)
+        .max()
+        .unwrap_or_default();
+
+    for count in counts {
+        // Anything above zero gets at least one block so it stands out from empty rows
+        let width = match count.count {
+            0 => 0,
+            n => (n * CHART_WIDTH / max).max(1),
+        };
+
+        let bar = format!("{} {}", "█".repeat(width as usize), count.count);
+        writeln!(f, "[*] {:>label_width$} | {}", count.name, bar.trim_start())?;
+    }
+
+    Ok(())
+}
+
+/// Counts coloured (WUBRG) and colourless (C) mana symbols in a card's mana
+/// cost. Hybrid symbols count towards each of their colours
+pub(crate) fn count_pips(card: &DbCard) -> [u32; COLOURS.len() + 1] {
+    let mut pips = [0; COLOURS.len() + 1];
+    let mana_cost = card.mana_cost.as_deref().unwrap_or_default();
+    for symbol in mana_cost.split(['{', '}']) {
+        for part in symbol.split('/') {
+            let colour = COLOURS.iter().chain(['C'].iter()).position(|c| {
+                let mut chars = part.chars();
+                chars.next() == Some(*c) && chars.next().is_none()
+            });
+
+            if let Some(colour) = colour {
+                pips[colour] += 1;
+            }
+        }
+    }
+
+    pips
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::DeckEntry;
+
+    fn card(type_line: &str, mana_cost: &str, cmc: f32, keywords: &str) -> DbCard {
+        DbCard {
+            type_line: Some(type_line.to_string()),
+            mana_cost: Some(mana_cost.to_string()),
+            cmc: Some(cmc),
+            keywords: Some(keywords.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn counts(counts: &[Count]) -> Vec<(&str, u32)> {
+        counts
+            .iter()
+            .map(|count| (count.name.as_str(), count.count))
+            .collect()
+    }
+
+    #[test]
+    fn counts_main_deck_and_command_zone() {
+        let deck = Deck::new(vec![
+            DeckEntry::new(1, "Emrakul, the Aeons Torn", DeckSection::Commander),
+            DeckEntry::new(4, "Lightning Bolt", DeckSection::Main),
+            DeckEntry::new(2, "Baneslayer Angel", DeckSection::Main),
+            DeckEntry::new(3, "Mountain", DeckSection::Main),
+            DeckEntry::new(1, "Unknown Card", DeckSection::Main),
+            DeckEntry::new(2, "Pyroblast", DeckSection::Sideboard),
+        ]);
+        let cards = vec![
+            Some(card(
+                "Legendary Creature — Eldrazi",
+                "{15}",
+                15.0,
+                r#"["Flying", "Annihilator"]"#,
+            )),
+            Some(card("Instant", "{R}", 1.0, "[]")),
+            Some(card(
+                "Creature — Angel",
+                "{3}{W}{W}",
+                5.0,
+                r#"["Flying", "Lifelink"]"#,
+            )),
+            Some(card("Basic Land — Mountain", "", 0.0, "[]")),
+            None,
+            Some(card("Instant", "{R}", 1.0, "[]")),
+        ];
+
+        let stats = DeckStats::new(&deck, &cards);
+
+        assert_eq!(stats.cards, 10);
+        assert_eq!(stats.lands, 3);
+        assert_eq!(stats.average_cmc, 2.9);
+        assert_eq!(stats.average_cmc_without_lands, 29.0 / 7.0);
+        assert_eq!(
+            counts(&stats.curve),
+            [
+                ("0", 0),
+                ("1", 4),
+                ("2", 0),
+                ("3", 0),
+                ("4", 0),
+                ("5", 2),
+                ("6", 0),
+                ("7+", 1)
+            ]
+        );
+        assert_eq!(
+            counts(&stats.pips),
+            [("W", 4), ("U", 0), ("B", 0), ("R", 4), ("G", 0), ("C", 0)]
+        );
+        assert_eq!(
+            counts(&stats.types),
+            [("Creature", 3), ("Instant", 4), ("Land", 3)]
+        );
+        assert_eq!(
+            counts(&stats.keywords),
+            [("Flying", 3), ("Lifelink", 2), ("Annihilator", 1)]
+        );
+        assert_eq!(stats.unknown, ["Unknown Card"]);
+    }
+
+    #[test]
+    fn handles_empty_deck() {
+        let stats = DeckStats::new(&Deck::new(Vec::new()), &[]);
+
+        assert_eq!(stats.cards, 0);
+        assert_eq!(stats.average_cmc, 0.0);
+        assert_eq!(stats.average_cmc_without_lands, 0.0);
+        assert!(stats.types.is_empty());
+    }
+
+    #[test]
+    fn counts_hybrid_and_colourless_pips() {
+        let pips = |mana_cost: &str| count_pips(&card("", mana_cost, 0.0, ""));
+
+        assert_eq!(pips("{2}{U}{U}"), [0, 2, 0, 0, 0, 0]);
+        assert_eq!(pips("{W/U}{2/B}{B/P}{C}"), [1, 1, 2, 0, 0, 1]);
+        assert_eq!(pips("{X}{R}{G} // {1}{G}"), [0, 0, 0, 1, 2, 0]);
+    }
+}
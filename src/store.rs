@@ -1,14 +1,63 @@
 use anyhow::{Context, Result};
-use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions};
+use sqlx::{
+    sqlite::{Sqlite, SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions},
+    QueryBuilder,
+};
 
 use std::str::FromStr;
 
 use crate::{
-    card::{Currency, DbCard, PricedCard, StoreValue},
-    utils::{get_project_dir, is_empty_entry},
+    card::{Currency, DbCard, Deck, DeckEntry, DeckSection, PricedCard, StoreValue},
+    collection::CollectionCard,
+    ledger::{LedgerEntry, Transaction},
+    query::{rarity_rank_sql, SearchQuery, SortOrder},
+    saved::{DeckRevision, DeckValue, SavedDeck},
+    utils::{escape_like, get_project_dir, is_empty_entry, normalise_name},
 };
 use std::path::PathBuf;
 
+/// Outcome of looking up a card name from user input
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Resolution {
+    Found(String),
+    Ambiguous(Vec<String>),
+    NotFound,
+}
+
+impl Resolution {
+    fn from_candidates(mut candidates: Vec<String>) -> Self {
+        match candidates.len() {
+            0 => Self::NotFound,
+            1 => Self::Found(candidates.remove(0)),
+            _ => Self::Ambiguous(candidates),
+        }
+    }
+}
+
+// Exact match on any face of a multi-faced card, bound to the normalised name
+// with LIKE wildcards escaped
+const FACE_QUERY: &str = r"select distinct name from cards where normalised_name like ?1 || ' // %' escape '\' or normalised_name like '% // ' || ?1 escape '\' or normalised_name like '% // ' || ?1 || ' // %' escape '\' order by name";
+
+// Lookups tried in order when resolving a non-exact card name, bound to the
+// normalised name with LIKE wildcards escaped
+const RESOLUTION_QUERIES: [&str; 2] = [
+    // Prefix of the full card name
+    r"select distinct name from cards where normalised_name like ?1 || '%' escape '\' order by name",
+    // Anywhere in the card name
+    r"select distinct name from cards where normalised_name like '%' || ?1 || '%' escape '\' order by name",
+];
+
+/// Quantity, name, section, set, collector number, foil and MTGO id of a saved card
+type DeckCardRow = (
+    u32,
+    String,
+    DeckSection,
+    Option<String>,
+    Option<String>,
+    bool,
+    Option<u32>,
+);
+
 #[derive(Debug, Clone)]
 pub(crate) struct MageDeck {
     pool: SqlitePool,
@@ -41,12 +90,26 @@ impl MageDeck {
 
         for card in cards {
             sqlx::query(
-                "insert into cards(id, name, set_tag, set_name, euro, euro_foil, usd, usd_foil, usd_etched, tix, cardmarket, cardhoarder, tcgplayer) values(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)"
+                "insert into cards(id, name, normalised_name, set_tag, set_name, collector_number, mtgo_id, type_line, oracle_text, mana_cost, cmc, colors, color_identity, rarity, artist, legalities, keywords, produced_mana, euro, euro_foil, usd, usd_foil, usd_etched, tix, cardmarket, cardhoarder, tcgplayer, scryfall_id) values(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28)"
             )
             .bind(card.id)
-            .bind(card.name)
+            .bind(&card.name)
+            .bind(card.name.as_deref().map(normalise_name))
             .bind(card.set)
             .bind(card.set_name)
+            .bind(card.collector_number)
+            .bind(card.mtgo_id)
+            .bind(card.type_line)
+            .bind(card.oracle_text)
+            .bind(card.mana_cost)
+            .bind(card.cmc)
+            .bind(card.colors)
+            .bind(card.color_identity)
+            .bind(card.rarity)
+            .bind(card.artist)
+            .bind(card.legalities)
+            .bind(card.keywords)
+            .bind(card.produced_mana)
             .bind(card.euro)
             .bind(card.euro_foil)
             .bind(card.usd)
@@ -56,42 +119,79 @@ impl MageDeck {
             .bind(card.cardmarket)
             .bind(card.cardhoarder)
             .bind(card.tcgplayer)
+            .bind(card.scryfall_id)
             .execute(&self.pool).await?;
         }
 
+        sqlx::query("insert into cards_fts(cards_fts) values('rebuild')")
+            .execute(&self.pool)
+            .await
+            .context("rebuilding search index")?;
+
         println!("[*] Database synced!");
         Ok(())
     }
 
+    /// Resolves a card name to a single card, preferring an exact match on the
+    /// full name, then on one face of a multi-faced card, then prefix matches
+    /// before falling back to a substring search
+    pub(crate) async fn resolve_name(
+        &mut self,
+        name: &str,
+        exact_match: bool,
+    ) -> Result<Resolution> {
+        let name = normalise_name(name);
+        let pattern = escape_like(&name);
+        let mut exact = self
+            .find_names(
+                "select distinct name from cards where normalised_name = ?1",
+                &name,
+            )
+            .await?;
+
+        if exact.is_empty() {
+            exact = self.find_names(FACE_QUERY, &pattern).await?;
+        }
+
+        if !exact.is_empty() || exact_match {
+            return Ok(Resolution::from_candidates(exact));
+        }
+
+        for query in RESOLUTION_QUERIES {
+            let candidates = self.find_names(query, &pattern).await?;
+            if !candidates.is_empty() {
+                return Ok(Resolution::from_candidates(candidates));
+            }
+        }
+
+        Ok(Resolution::NotFound)
+    }
+
+    async fn find_names(&mut self, query: &str, name: &str) -> Result<Vec<String>> {
+        let names: Vec<(String,)> = sqlx::query_as(query)
+            .bind(name)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(names.into_iter().map(|(name,)| name).collect())
+    }
+
+    /// Gets the cheapest printing of a card by its exact name
     pub(crate) async fn get_cheapest_card(
         &mut self,
         name: &str,
         currency: Currency,
-        exact_match: bool,
     ) -> Result<Option<PricedCard>> {
-        let purchase_site = match currency {
-            Currency::Euro | Currency::EuroFoil => "cardmarket",
-            Currency::Usd | Currency::UsdFoil | Currency::UsdEtched => "tcgplayer",
-            Currency::Tix => "cardhoarder",
-        };
-
-        let query = if exact_match {
-            format!(
-                "select name, set_tag, set_name, min({}), {} from cards where name = '{}'",
-                currency.to_string(),
-                purchase_site,
-                name
-            )
-        } else {
-            format!(
-                "select name, set_tag, set_name, min({}), {} from cards where name like '%{}%'",
-                currency.to_string(),
-                purchase_site,
-                name
-            )
-        };
+        let mut query = QueryBuilder::<Sqlite>::new("select name, set_tag, set_name, min(");
+        query
+            .push(currency.column())
+            .push("), ")
+            .push(currency.purchase_site())
+            .push(", type_line from cards where normalised_name = ")
+            .push_bind(normalise_name(name));
 
-        let record: Vec<StoreValue> = sqlx::query_as::<_, StoreValue>(&query)
+        let record: Vec<StoreValue> = query
+            .build_query_as::<StoreValue>()
             .fetch_all(&self.pool)
             .await?;
 
@@ -103,15 +203,477 @@ impl MageDeck {
         Ok(Some(card))
     }
 
-    pub(crate) async fn get_cards(&mut self, name: &str) -> Result<Vec<DbCard>> {
-        let query = format!("select * from cards where name like '%{name}%'");
-        let result: Vec<DbCard> = sqlx::query_as::<_, DbCard>(&query)
+    /// Searches the cards matching a query, with prices in the given currency
+    pub(crate) async fn search(
+        &mut self,
+        search: &SearchQuery,
+        currency: Currency,
+        sort: SortOrder,
+        descending: bool,
+        limit: u32,
+    ) -> Result<Vec<PricedCard>> {
+        let mut query = QueryBuilder::<Sqlite>::new("select c.name, c.set_tag, c.set_name, c.");
+        query
+            .push(currency.column())
+            .push(", c.")
+            .push(currency.purchase_site())
+            .push(", c.type_line from cards c where ");
+        search.push_condition(&mut query)?;
+
+        let direction = if descending { " desc" } else { " asc" };
+        query.push(" order by ");
+        match (sort, search.text_match()) {
+            (SortOrder::Relevance, Some(text_match)) => {
+                // Cards matched without any text (e.g. by a filter on the other
+                // side of an `or`) have no relevance and always go last
+                let relevance = "(select bm25(cards_fts) from cards_fts where cards_fts match ";
+                query
+                    .push(relevance)
+                    .push_bind(text_match.clone())
+                    .push(" and rowid = c.rowid) is null, ")
+                    .push(relevance)
+                    .push_bind(text_match)
+                    .push(" and rowid = c.rowid)")
+                    .push(direction);
+            }
+            (SortOrder::Relevance | SortOrder::Name, _) => {
+                query.push("c.name").push(direction);
+            }
+            (SortOrder::Price, _) => {
+                // Cards without a price in this currency always go last
+                query
+                    .push("c.")
+                    .push(currency.column())
+                    .push(" is null, c.")
+                    .push(currency.column())
+                    .push(direction);
+            }
+            (SortOrder::Cmc, _) => {
+                query.push("c.cmc").push(direction);
+            }
+            (SortOrder::Rarity, _) => {
+                query.push(rarity_rank_sql("c.rarity")).push(direction);
+            }
+            (SortOrder::Set, _) => {
+                query.push("c.set_tag").push(direction);
+            }
+        }
+
+        query.push(", c.name limit ").push_bind(limit);
+
+        let records: Vec<StoreValue> = query
+            .build_query_as::<StoreValue>()
             .fetch_all(&self.pool)
             .await?;
 
+        let cards = records
+            .into_iter()
+            .map(|record| PricedCard::new(record, currency))
+            .collect();
+
+        Ok(cards)
+    }
+
+    pub(crate) async fn get_cards(&mut self, name: &str) -> Result<Vec<DbCard>> {
+        let result: Vec<DbCard> = sqlx::query_as::<_, DbCard>(
+            r"select * from cards where normalised_name like '%' || ?1 || '%' escape '\'",
+        )
+        .bind(escape_like(&normalise_name(name)))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    pub(crate) async fn get_card(&mut self, name: &str) -> Result<Option<DbCard>> {
+        let result: Option<DbCard> =
+            sqlx::query_as::<_, DbCard>("select * from cards where normalised_name = ?1 limit 1")
+                .bind(normalise_name(name))
+                .fetch_optional(&self.pool)
+                .await?;
+
         Ok(result)
     }
 
+    /// Gets the synced printing with this Scryfall id
+    pub(crate) async fn get_card_by_scryfall_id(&mut self, id: &str) -> Result<Option<DbCard>> {
+        let result: Option<DbCard> =
+            sqlx::query_as::<_, DbCard>("select * from cards where scryfall_id = ?1 limit 1")
+                .bind(id.to_lowercase())
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(result)
+    }
+
+    /// Gets the synced printing with this set code and collector number
+    pub(crate) async fn get_printing(
+        &mut self,
+        set: &str,
+        collector_number: &str,
+    ) -> Result<Option<DbCard>> {
+        let result: Option<DbCard> = sqlx::query_as::<_, DbCard>(
+            "select * from cards where set_tag = ?1 and collector_number = ?2 limit 1",
+        )
+        .bind(set.to_uppercase())
+        .bind(collector_number)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    pub(crate) async fn get_card_names(&mut self) -> Result<Vec<String>> {
+        let names: Vec<(String,)> = sqlx::query_as("select distinct name from cards")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(names.into_iter().map(|(name,)| name).collect())
+    }
+
+    /// Adds copies of a printing to the collection, merging them into any entry
+    /// with the same printing, finish, condition and language
+    pub(crate) async fn add_to_collection(&mut self, card: &CollectionCard) -> Result<()> {
+        sqlx::query(
+            "insert into collection(name, normalised_name, set_tag, collector_number, finish, condition, language, quantity) values(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8) on conflict(normalised_name, set_tag, collector_number, finish, condition, language) do update set quantity = quantity + excluded.quantity"
+        )
+        .bind(&card.name)
+        .bind(normalise_name(&card.name))
+        .bind(&card.set)
+        .bind(&card.collector_number)
+        .bind(card.finish)
+        .bind(card.condition)
+        .bind(&card.language)
+        .bind(card.quantity)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Removes up to `card.quantity` copies from the collection, from any printing
+    /// when no set or collector number is given. Returns the number of copies removed
+    pub(crate) async fn remove_from_collection(&mut self, card: &CollectionCard) -> Result<u32> {
+        let mut query = QueryBuilder::<Sqlite>::new(
+            "select id, quantity from collection where normalised_name = ",
+        );
+        query
+            .push_bind(normalise_name(&card.name))
+            .push(" and finish = ")
+            .push_bind(card.finish)
+            .push(" and condition = ")
+            .push_bind(card.condition)
+            .push(" and language = ")
+            .push_bind(&card.language);
+
+        if !card.set.is_empty() {
+            query.push(" and set_tag = ").push_bind(&card.set);
+        }
+
+        if !card.collector_number.is_empty() {
+            query
+                .push(" and collector_number = ")
+                .push_bind(&card.collector_number);
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let rows: Vec<(i64, u32)> = query
+            .push(" order by id")
+            .build_query_as()
+            .fetch_all(&mut *tx)
+            .await?;
+
+        let mut remaining = card.quantity;
+        for (id, quantity) in rows {
+            if remaining == 0 {
+                break;
+            }
+
+            let removed = quantity.min(remaining);
+            if removed == quantity {
+                sqlx::query("delete from collection where id = ?1")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            } else {
+                sqlx::query("update collection set quantity = quantity - ?1 where id = ?2")
+                    .bind(removed)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            remaining -= removed;
+        }
+
+        tx.commit().await?;
+        Ok(card.quantity - remaining)
+    }
+
+    pub(crate) async fn get_collection(&mut self) -> Result<Vec<CollectionCard>> {
+        let cards: Vec<CollectionCard> = sqlx::query_as(
+            "select name, set_tag, collector_number, finish, condition, language, quantity from collection order by name, set_tag, collector_number",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(cards)
+    }
+
+    /// Records a purchase or sale of a card, dated today when no date is given.
+    /// Returns the id of the new ledger entry
+    pub(crate) async fn add_to_ledger(
+        &mut self,
+        name: &str,
+        kind: Transaction,
+        quantity: u32,
+        amount: f32,
+        currency: Currency,
+        date: Option<&str>,
+    ) -> Result<i64> {
+        let result = sqlx::query(
+            "insert into ledger(name, normalised_name, kind, quantity, amount, currency, date) values(?1, ?2, ?3, ?4, ?5, ?6, coalesce(?7, date('now', 'localtime')))"
+        )
+        .bind(name)
+        .bind(normalise_name(name))
+        .bind(kind)
+        .bind(quantity)
+        .bind(amount)
+        .bind(currency)
+        .bind(date)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Removes a ledger entry, returning whether it existed
+    pub(crate) async fn remove_from_ledger(&mut self, id: i64) -> Result<bool> {
+        let result = sqlx::query("delete from ledger where id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub(crate) async fn get_ledger(&mut self) -> Result<Vec<LedgerEntry>> {
+        let entries: Vec<LedgerEntry> = sqlx::query_as(
+            "select id, name, kind, quantity, amount, currency, date from ledger order by date, id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(entries)
+    }
+
+    /// Saves a deck as the next revision under a name, returning the revision number
+    pub(crate) async fn save_deck(&mut self, name: &str, deck: &Deck) -> Result<u32> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("insert into decks(name) values(?1) on conflict(name) do nothing")
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+
+        let (deck_id, revision): (i64, u32) = sqlx::query_as(
+            "update decks set last_revision = last_revision + 1 where name = ?1 returning id, last_revision",
+        )
+        .bind(name)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let revision_id =
+            sqlx::query("insert into deck_revisions(deck_id, revision) values(?1, ?2)")
+                .bind(deck_id)
+                .bind(revision)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid();
+
+        for card in deck.cards.iter() {
+            sqlx::query(
+                "insert into deck_cards(revision_id, quantity, name, section, set_tag, collector_number, foil, mtgo_id) values(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"
+            )
+            .bind(revision_id)
+            .bind(card.quantity)
+            .bind(&card.name)
+            .bind(card.section)
+            .bind(&card.set)
+            .bind(&card.collector_number)
+            .bind(card.foil)
+            .bind(card.mtgo_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(revision)
+    }
+
+    /// Loads a saved deck, at its latest revision when none is given. Returns the
+    /// revision loaded along with the deck
+    pub(crate) async fn get_saved_deck(
+        &mut self,
+        name: &str,
+        revision: Option<u32>,
+    ) -> Result<Option<(u32, Deck)>> {
+        let mut query = QueryBuilder::<Sqlite>::new(
+            "select r.id, r.revision, d.name from deck_revisions r join decks d on d.id = r.deck_id where d.name = ",
+        );
+        query.push_bind(name);
+        if let Some(revision) = revision {
+            query.push(" and r.revision = ").push_bind(revision);
+        }
+
+        let found: Option<(i64, u32, String)> = query
+            .push(" order by r.revision desc limit 1")
+            .build_query_as()
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let Some((revision_id, revision, name)) = found else {
+            return Ok(None);
+        };
+
+        let rows: Vec<DeckCardRow> = sqlx::query_as(
+            "select quantity, name, section, set_tag, collector_number, foil, mtgo_id from deck_cards where revision_id = ?1 order by id",
+        )
+        .bind(revision_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let cards = rows
+            .into_iter()
+            .map(
+                |(quantity, card_name, section, set, collector_number, foil, mtgo_id)| {
+                    let mut entry = DeckEntry::new(quantity, card_name, section);
+                    entry.set = set;
+                    entry.collector_number = collector_number;
+                    entry.foil = foil;
+                    entry.mtgo_id = mtgo_id;
+                    entry
+                },
+            )
+            .collect();
+
+        let mut deck = Deck::new(cards);
+        deck.name = Some(name);
+        Ok(Some((revision, deck)))
+    }
+
+    pub(crate) async fn get_saved_decks(&mut self) -> Result<Vec<SavedDeck>> {
+        let decks: Vec<SavedDeck> = sqlx::query_as(
+            "select d.name, (select count(*) from deck_revisions where deck_id = d.id) as revisions, r.saved_at, coalesce((select sum(quantity) from deck_cards where revision_id = r.id), 0) as cards from decks d join deck_revisions r on r.deck_id = d.id where r.revision = (select max(revision) from deck_revisions where deck_id = d.id) order by d.name",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(decks)
+    }
+
+    pub(crate) async fn get_deck_revisions(&mut self, name: &str) -> Result<Vec<DeckRevision>> {
+        let revisions: Vec<DeckRevision> = sqlx::query_as(
+            "select r.revision, r.saved_at, coalesce((select sum(quantity) from deck_cards where revision_id = r.id), 0) as cards from deck_revisions r join decks d on d.id = r.deck_id where d.name = ?1 order by r.revision",
+        )
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(revisions)
+    }
+
+    /// Removes a saved deck, or a single revision of it, returning whether anything
+    /// was removed. Decks are removed along with their last revision
+    pub(crate) async fn remove_saved_deck(
+        &mut self,
+        name: &str,
+        revision: Option<u32>,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let removed = match revision {
+            Some(revision) => {
+                sqlx::query(
+                    "delete from deck_revisions where revision = ?2 and deck_id = (select id from decks where name = ?1)",
+                )
+                .bind(name)
+                .bind(revision)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected()
+                    > 0
+            }
+            None => false,
+        };
+
+        let deck_removed = sqlx::query(
+            "delete from decks where name = ?1 and (?2 or not exists (select 1 from deck_revisions where deck_id = decks.id))",
+        )
+        .bind(name)
+        .bind(revision.is_none())
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+
+        tx.commit().await?;
+        Ok(removed || deck_removed)
+    }
+
+    pub(crate) async fn record_deck_value(
+        &mut self,
+        name: &str,
+        revision: u32,
+        currency: Currency,
+        value: f32,
+        unpriced: u32,
+    ) -> Result<()> {
+        sqlx::query(
+            "insert into deck_values(deck_id, revision, currency, value, unpriced) select id, ?2, ?3, ?4, ?5 from decks where name = ?1",
+        )
+        .bind(name)
+        .bind(revision)
+        .bind(currency)
+        .bind(value)
+        .bind(unpriced)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Values recorded for a saved deck in a currency, oldest first
+    pub(crate) async fn get_deck_values(
+        &mut self,
+        name: &str,
+        currency: Currency,
+    ) -> Result<Vec<DeckValue>> {
+        let values: Vec<DeckValue> = sqlx::query_as(
+            "select v.revision, v.value, v.unpriced, v.recorded_at from deck_values v join decks d on d.id = v.deck_id where d.name = ?1 and v.currency = ?2 order by v.id",
+        )
+        .bind(name)
+        .bind(currency)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(values)
+    }
+
+    /// Last value recorded for a saved deck in a currency
+    pub(crate) async fn latest_deck_value(
+        &mut self,
+        name: &str,
+        currency: Currency,
+    ) -> Result<Option<DeckValue>> {
+        let value: Option<DeckValue> = sqlx::query_as(
+            "select v.revision, v.value, v.unpriced, v.recorded_at from deck_values v join decks d on d.id = v.deck_id where d.name = ?1 and v.currency = ?2 order by v.id desc limit 1",
+        )
+        .bind(name)
+        .bind(currency)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(value)
+    }
+
     async fn setup_db(pool: &SqlitePool) -> Result<()> {
         sqlx::migrate!("./migrations")
             .run(pool)
@@ -121,3 +683,328 @@ impl MageDeck {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::Finish;
+
+    /// A card in a test set, priced at 1€
+    fn test_card(name: &str) -> DbCard {
+        DbCard {
+            id: Some(name.to_string()),
+            name: Some(name.to_string()),
+            set: Some("tst".to_string()),
+            set_name: Some("Test Set".to_string()),
+            euro: Some(1.0),
+            ..Default::default()
+        }
+    }
+
+    /// Creates an in memory database holding the given cards
+    async fn test_db_with(cards: Vec<DbCard>) -> MageDeck {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        MageDeck::setup_db(&pool).await.unwrap();
+        let mut db = MageDeck { pool };
+        db.sync(cards).await.unwrap();
+        db
+    }
+
+    /// Creates an in memory database holding a card for each name
+    async fn test_db(names: &[&str]) -> MageDeck {
+        test_db_with(names.iter().map(|name| test_card(name)).collect()).await
+    }
+
+    async fn search_names(db: &mut MageDeck, query: &str, sort: SortOrder) -> Vec<String> {
+        let query = SearchQuery::parse(query).unwrap();
+        db.search(&query, Currency::Euro, sort, false, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|card| card.name.unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn resolves_any_face_before_prefix_matches() {
+        let mut db = test_db(&["Fire // Ice", "Fireball", "Ice Cage", "Icingdeath"]).await;
+        for name in ["Fire", "ice", "fire // ice"] {
+            let resolution = db.resolve_name(name, false).await.unwrap();
+            assert_eq!(resolution, Resolution::Found("Fire // Ice".to_string()));
+        }
+
+        let resolution = db.resolve_name("Ice", true).await.unwrap();
+        assert_eq!(resolution, Resolution::Found("Fire // Ice".to_string()));
+        let resolution = db.resolve_name("Fireb", false).await.unwrap();
+        assert_eq!(resolution, Resolution::Found("Fireball".to_string()));
+    }
+
+    #[tokio::test]
+    async fn prefers_full_name_over_face() {
+        let mut db = test_db(&["Fire // Ice", "Ice"]).await;
+        let resolution = db.resolve_name("Ice", false).await.unwrap();
+        assert_eq!(resolution, Resolution::Found("Ice".to_string()));
+    }
+
+    const SPECIAL_NAMES: [&str; 4] = [
+        "Ach! Hans, Run!",
+        "Kongming, \"Sleeping Dragon\"",
+        "100%",
+        "_____",
+    ];
+
+    #[tokio::test]
+    async fn resolves_names_with_special_characters_literally() {
+        let mut db = test_db(&SPECIAL_NAMES).await;
+        for name in SPECIAL_NAMES {
+            for exact_match in [true, false] {
+                let resolution = db.resolve_name(name, exact_match).await.unwrap();
+                assert_eq!(resolution, Resolution::Found(name.to_string()));
+            }
+        }
+
+        // Wildcards in partial names only match themselves
+        let resolution = db.resolve_name("__", false).await.unwrap();
+        assert_eq!(resolution, Resolution::Found("_____".to_string()));
+        let resolution = db.resolve_name("%", false).await.unwrap();
+        assert_eq!(resolution, Resolution::Found("100%".to_string()));
+        let resolution = db.resolve_name("\"sleeping", false).await.unwrap();
+        assert_eq!(
+            resolution,
+            Resolution::Found("Kongming, \"Sleeping Dragon\"".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn gets_cheapest_card_by_exact_name() {
+        let mut db = test_db(&SPECIAL_NAMES).await;
+        for name in SPECIAL_NAMES {
+            let card = db.get_cheapest_card(name, Currency::Euro).await.unwrap();
+            assert_eq!(card.unwrap().name.as_deref(), Some(name));
+        }
+
+        for name in ["%", "_", "100", "Ach!"] {
+            let card = db.get_cheapest_card(name, Currency::Euro).await.unwrap();
+            assert!(card.is_none(), "'{name}' matched a card");
+        }
+    }
+
+    #[tokio::test]
+    async fn sorts_cards_matched_without_text_last() {
+        let mut db = test_db_with(vec![
+            DbCard {
+                colors: Some("R".to_string()),
+                ..test_card("Shock")
+            },
+            test_card("Lightning Bolt"),
+            test_card("Lightning Helix"),
+            test_card("Opt"),
+        ])
+        .await;
+
+        let names = search_names(&mut db, "c:r or bolt", SortOrder::Relevance).await;
+        assert_eq!(names, ["Lightning Bolt", "Shock"]);
+
+        let names = search_names(&mut db, "bolt or helix or c:r", SortOrder::Relevance).await;
+        assert_eq!(names.len(), 3);
+        assert_eq!(names.last().unwrap(), "Shock");
+    }
+
+    #[tokio::test]
+    async fn sorts_and_filters_rarities_like_scryfall() {
+        let cards = [
+            ("Black Lotus", "bonus"),
+            ("Jace", "mythic"),
+            ("Shock", "common"),
+            ("Time Walk", "special"),
+            ("Opt", "rare"),
+        ]
+        .into_iter()
+        .map(|(name, rarity)| DbCard {
+            rarity: Some(rarity.to_string()),
+            ..test_card(name)
+        })
+        .collect();
+
+        let mut db = test_db_with(cards).await;
+        let names = search_names(&mut db, "", SortOrder::Rarity).await;
+        assert_eq!(names, ["Shock", "Opt", "Time Walk", "Jace", "Black Lotus"]);
+
+        let names = search_names(&mut db, "r>rare r<bonus", SortOrder::Rarity).await;
+        assert_eq!(names, ["Time Walk", "Jace"]);
+    }
+
+    #[tokio::test]
+    async fn counts_saved_deck_revisions() {
+        let mut db = test_db(&["Opt"]).await;
+        let mut deck = Deck::new(vec![DeckEntry::new(4, "Opt", DeckSection::Main)]);
+        for _ in 0..3 {
+            db.save_deck("tempo", &deck).await.unwrap();
+        }
+
+        deck.cards[0].quantity = 2;
+        assert_eq!(db.save_deck("tempo", &deck).await.unwrap(), 4);
+        assert!(db.remove_saved_deck("tempo", Some(1)).await.unwrap());
+
+        let decks = db.get_saved_decks().await.unwrap();
+        assert_eq!(decks.len(), 1);
+        assert_eq!(decks[0].revisions, 3);
+        assert_eq!(decks[0].cards, 2);
+    }
+
+    #[tokio::test]
+    async fn never_reuses_removed_revisions() {
+        let mut db = test_db(&["Opt"]).await;
+        let deck = Deck::new(vec![DeckEntry::new(4, "Opt", DeckSection::Main)]);
+        assert_eq!(db.save_deck("tempo", &deck).await.unwrap(), 1);
+        assert_eq!(db.save_deck("tempo", &deck).await.unwrap(), 2);
+        assert!(db.remove_saved_deck("tempo", Some(2)).await.unwrap());
+        assert_eq!(db.save_deck("Tempo", &deck).await.unwrap(), 3);
+
+        let (revision, _) = db.get_saved_deck("tempo", None).await.unwrap().unwrap();
+        assert_eq!(revision, 3);
+        assert!(db.get_saved_deck("tempo", Some(2)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn records_deck_values_per_currency() {
+        let mut db = test_db(&["Opt"]).await;
+        let deck = Deck::new(vec![DeckEntry::new(4, "Opt", DeckSection::Main)]);
+        db.save_deck("tempo", &deck).await.unwrap();
+        assert!(db
+            .latest_deck_value("tempo", Currency::Euro)
+            .await
+            .unwrap()
+            .is_none());
+
+        db.record_deck_value("tempo", 1, Currency::Euro, 4.0, 0)
+            .await
+            .unwrap();
+        db.record_deck_value("tempo", 1, Currency::Usd, 5.0, 0)
+            .await
+            .unwrap();
+        db.record_deck_value("tempo", 1, Currency::Euro, 3.0, 1)
+            .await
+            .unwrap();
+
+        let values = db.get_deck_values("tempo", Currency::Euro).await.unwrap();
+        let values: Vec<f32> = values.iter().map(|value| value.value).collect();
+        assert_eq!(values, [4.0, 3.0]);
+
+        let latest = db
+            .latest_deck_value("tempo", Currency::Euro)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((latest.value, latest.unpriced), (3.0, 1));
+        assert!(db
+            .latest_deck_value("burn", Currency::Euro)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    fn collection_card(name: &str, set: &str, quantity: u32) -> CollectionCard {
+        CollectionCard {
+            set: set.to_string(),
+            ..CollectionCard::new(name, quantity)
+        }
+    }
+
+    async fn collection(db: &mut MageDeck) -> Vec<String> {
+        db.get_collection()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|card| card.to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn merges_copies_of_the_same_printing() {
+        let mut db = test_db(&[]).await;
+        db.add_to_collection(&collection_card("Lightning Bolt", "M10", 2))
+            .await
+            .unwrap();
+        db.add_to_collection(&collection_card("lightning bolt", "M10", 1))
+            .await
+            .unwrap();
+        db.add_to_collection(&CollectionCard {
+            collector_number: "146".to_string(),
+            finish: Finish::Foil,
+            ..collection_card("Lightning Bolt", "M10", 1)
+        })
+        .await
+        .unwrap();
+        db.add_to_collection(&collection_card("Lightning Bolt", "2X2", 1))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            collection(&mut db).await,
+            [
+                "1x Lightning Bolt (2X2) - nonfoil, NM, en",
+                "3x Lightning Bolt (M10) - nonfoil, NM, en",
+                "1x Lightning Bolt (M10 146) - foil, NM, en",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn removes_copies_from_matching_printings() {
+        let mut db = test_db(&[]).await;
+        for card in [
+            collection_card("Jötun Grunt", "CSP", 2),
+            collection_card("Jötun Grunt", "MMA", 2),
+            collection_card("Lightning Bolt", "M10", 1),
+        ] {
+            db.add_to_collection(&card).await.unwrap();
+        }
+
+        // Only the given set is touched when one is given
+        let removed = db
+            .remove_from_collection(&collection_card("Jotun Grunt", "MMA", 1))
+            .await
+            .unwrap();
+        assert_eq!(removed, 1);
+
+        // Otherwise copies come from any printing, oldest first
+        let removed = db
+            .remove_from_collection(&collection_card("jotun grunt", "", 2))
+            .await
+            .unwrap();
+        assert_eq!(removed, 2);
+        assert_eq!(
+            collection(&mut db).await,
+            [
+                "1x Jötun Grunt (MMA) - nonfoil, NM, en",
+                "1x Lightning Bolt (M10) - nonfoil, NM, en",
+            ]
+        );
+
+        // Removing more than is held removes what there is
+        let removed = db
+            .remove_from_collection(&collection_card("Lightning Bolt", "", 4))
+            .await
+            .unwrap();
+        assert_eq!(removed, 1);
+
+        let removed = db
+            .remove_from_collection(&CollectionCard {
+                finish: Finish::Foil,
+                ..collection_card("Jötun Grunt", "", 1)
+            })
+            .await
+            .unwrap();
+        assert_eq!(removed, 0);
+        assert_eq!(
+            collection(&mut db).await,
+            ["1x Jötun Grunt (MMA) - nonfoil, NM, en"]
+        );
+    }
+}
@@ -1,8 +1,8 @@
-use anyhow::{Context, Result};
-use std::path::{Path, PathBuf};
-use tokio::fs;
+use anyhow::Result;
+use std::path::PathBuf;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
 
-use crate::card::{Deck, DeckEntry, StoreValue, BASIC_LANDS};
+use crate::card::StoreValue;
 
 pub(crate) fn get_project_dir() -> Result<PathBuf> {
     let Some(home) = dirs::home_dir() else {
@@ -13,76 +13,106 @@ pub(crate) fn get_project_dir() -> Result<PathBuf> {
 }
 
 pub(crate) fn is_empty_entry(entry: &StoreValue) -> bool {
-    let (name, set_tag, set_name, _, _) = entry;
+    let (name, set_tag, set_name, _, _, _) = entry;
     if name.is_none() || set_tag.is_none() || set_name.is_none() {
         return true;
     }
 
-    return false;
+    false
 }
 
-pub(crate) fn sanitise(s: &str) -> String {
-    s.replace("'", "''")
+/// Escapes LIKE wildcards so user input is matched literally (used with `escape '\'`)
+pub(crate) fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
 }
 
-pub(crate) async fn load_deck(deck: impl AsRef<Path>) -> Result<Deck> {
-    let content = fs::read_to_string(deck)
-        .await
-        .context("loading deck file")?;
-
-    let cards = content
-        .split('\n')
-        .into_iter()
-        .filter_map(|line| {
-            let line = &trim_entry(line);
-            if !is_valid_deck_entry(line) || is_basic_land(line) {
-                return None;
-            }
-
-            let Some((quantity, name)) = line.split_once(' ') else {
-                return None;
-            };
-
-            let quantity = quantity.parse::<i8>().unwrap_or(1);
-            let name = sanitise(name);
-            Some((quantity, name.to_string()))
-        })
-        .collect::<Vec<DeckEntry>>();
-
-    let deck = Deck::new(cards);
-    Ok(deck)
+/// Normalises a CSV header or value for comparison by dropping everything but
+/// letters and digits and lowercasing (e.g. `Collector Number` -> `collectornumber`)
+pub(crate) fn normalise_header(header: &str) -> String {
+    header
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
 }
 
-fn is_valid_deck_entry(card: &str) -> bool {
-    if card == "Deck" || card == "Sideboard" || card.len() == 0 || card.starts_with("//") {
-        return false;
+/// Parses an amount of money or a percentage, which can't be negative
+pub(crate) fn parse_non_negative(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(amount) if amount.is_finite() && amount >= 0.0 => Ok(amount),
+        Ok(_) => Err(format!("'{value}' can't be negative")),
+        Err(_) => Err(format!("'{value}' isn't a number")),
     }
-
-    true
 }
 
-fn is_basic_land(card: &str) -> bool {
-    let card = card.to_lowercase();
+/// Lists candidate card names for an ambiguous lookup, truncating long lists
+pub(crate) fn format_candidates(candidates: &[String]) -> String {
+    const MAX_CANDIDATES: usize = 10;
 
-    for land in BASIC_LANDS {
-        if card.contains(land) {
-            return true;
-        }
+    let mut listed = candidates
+        .iter()
+        .take(MAX_CANDIDATES)
+        .map(|c| c.as_str())
+        .collect::<Vec<&str>>()
+        .join(", ");
+
+    if candidates.len() > MAX_CANDIDATES {
+        listed.push_str(&format!(" and {} more", candidates.len() - MAX_CANDIDATES));
     }
 
-    false
+    listed
 }
 
-fn trim_entry(mut entry: &str) -> String {
-    entry = entry.trim();
-    if let Some(removed_sb) = entry.strip_prefix("SB: ") {
-        entry = removed_sb;
+/// Normalises a card name for lookups by stripping accents, expanding ligatures,
+/// straightening curly quotes and lowercasing (e.g. `Jötun Grunt` -> `jotun grunt`)
+pub(crate) fn normalise_name(name: &str) -> String {
+    let mut normalised = String::with_capacity(name.len());
+    for c in name.nfkd().filter(|c| !is_combining_mark(*c)) {
+        match c {
+            'Æ' | 'æ' => normalised.push_str("ae"),
+            'Œ' | 'œ' => normalised.push_str("oe"),
+            '‘' | '’' | '`' | '´' => normalised.push('\''),
+            '“' | '”' => normalised.push('"'),
+            c => normalised.extend(c.to_lowercase()),
+        }
     }
 
-    if let Some(removed_commander) = entry.strip_suffix("# !Commander") {
-        entry = removed_commander;
+    normalised
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Checks a card's type line for the `Basic` supertype (e.g. `Basic Land — Island`,
+/// `Basic Snow Land — Forest` or `Basic Land` for Wastes)
+pub(crate) fn is_basic_land(type_line: &str) -> bool {
+    let supertypes = type_line.split('—').next().unwrap_or_default();
+    let mut types = supertypes.split_whitespace();
+
+    types.clone().any(|t| t == "Basic") && types.any(|t| t == "Land")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_negative_amounts() {
+        assert_eq!(parse_non_negative("1.50"), Ok(1.5));
+        assert_eq!(parse_non_negative("0"), Ok(0.0));
+        assert!(parse_non_negative("-0.5").is_err());
+        assert!(parse_non_negative("NaN").is_err());
+        assert!(parse_non_negative("inf").is_err());
+        assert!(parse_non_negative("ten").is_err());
     }
 
-    entry = entry.trim();
-    entry.to_string()
+    #[test]
+    fn escapes_like_wildcards() {
+        assert_eq!(escape_like("100%"), r"100\%");
+        assert_eq!(escape_like("_____"), r"\_\_\_\_\_");
+        assert_eq!(escape_like(r"a\b"), r"a\\b");
+        assert_eq!(escape_like("Ach! Hans, Run!"), "Ach! Hans, Run!");
+    }
 }